@@ -0,0 +1,113 @@
+use bevy::{ecs::system::System, prelude::*};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use homekoi::{
+    animation::{link_animation_player_system, FishAnimationLink},
+    fishes::Fish,
+    forces::Moveable,
+    random::random_direction,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const FISH_COUNTS: [usize; 3] = [1_000, 5_000, 10_000];
+/// Roughly the number of bones and meshes in a fish scene.
+const SCENE_NODES: usize = 30;
+
+/// Fish with a stand-in scene hierarchy: an animation player at the scene root and
+/// a chain of bones below it.
+fn pond(fish_count: usize) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..fish_count {
+        let fish = app
+            .world
+            .spawn(Fish::new_headless_npc(Transform::default()))
+            .insert(Moveable {
+                velocity: 10.0 * random_direction(&mut rng) * rng.gen::<f32>(),
+            })
+            .id();
+
+        let mut parent = app
+            .world
+            .spawn((TransformBundle::default(), AnimationPlayer::default()))
+            .id();
+        app.world.entity_mut(fish).push_children(&[parent]);
+        for _ in 1..SCENE_NODES {
+            let bone = app.world.spawn(TransformBundle::default()).id();
+            app.world.entity_mut(parent).push_children(&[bone]);
+            parent = bone;
+        }
+    }
+
+    let mut link = IntoSystem::into_system(link_animation_player_system);
+    link.initialize(&mut app.world);
+    link.run((), &mut app.world);
+    link.apply_buffers(&mut app.world);
+
+    app
+}
+
+/// The per-fish work both lookups are followed by.
+fn animate(player: &mut AnimationPlayer, moveable: &Moveable) {
+    player.set_speed(1.0 + moveable.velocity.length());
+}
+
+/// How the animator found each fish's player before it was linked: a walk over
+/// the whole scene of every fish, every frame.
+fn descendant_walk_lookup_system(
+    fishes: Query<(Entity, &Moveable), With<Fish>>,
+    children: Query<&Children>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    fishes.for_each(|(entity, moveable)| {
+        for child in children.iter_descendants(entity) {
+            if let Ok(mut player) = players.get_mut(child) {
+                animate(&mut player, moveable);
+            }
+        }
+    });
+}
+
+/// The lookup `fish_animator_system` uses: the player linked when the scene spawned.
+fn linked_lookup_system(
+    fishes: Query<(&Moveable, &FishAnimationLink), With<Fish>>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    fishes.for_each(|(moveable, link)| {
+        if let Ok(mut player) = players.get_mut(link.0) {
+            animate(&mut player, moveable);
+        }
+    });
+}
+
+fn bench_system<Params>(
+    c: &mut Criterion,
+    name: &str,
+    system: impl IntoSystem<(), (), Params> + Copy,
+) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+
+    for fish_count in FISH_COUNTS {
+        let mut app = pond(fish_count);
+        let mut system = IntoSystem::into_system(system);
+        system.initialize(&mut app.world);
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(fish_count),
+            &fish_count,
+            |b, _| b.iter(|| system.run((), &mut app.world)),
+        );
+    }
+
+    group.finish();
+}
+
+fn bench_player_lookup(c: &mut Criterion) {
+    bench_system(c, "descendant_walk_lookup", descendant_walk_lookup_system);
+    bench_system(c, "linked_lookup", linked_lookup_system);
+}
+
+criterion_group!(benches, bench_player_lookup);
+criterion_main!(benches);
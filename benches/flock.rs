@@ -0,0 +1,143 @@
+use std::time::{Duration, Instant};
+
+use bevy::{
+    ecs::{event::Events, system::System},
+    prelude::*,
+};
+use bevy_rapier3d::prelude::{CollisionEvent, CollisionEventFlags};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use homekoi::{
+    fishes::Fish,
+    forces::{alignment_force_system, cohesion_force_system, separation_force_system},
+    groups::{group_by_proximity_system, Groupable},
+    headless::HeadlessSimPlugin,
+    random::random_direction,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const FISH_COUNTS: [usize; 3] = [1_000, 10_000, 50_000];
+// Keeps roughly two neighbours within grouping distance of each fish at any pond size.
+const AREA_PER_FISH: f32 = 150.0;
+const WARMUP_TICKS: usize = 5;
+
+/// A headless pond of `fish_count` NPCs, stepped a few ticks so physics and groups are populated.
+fn pond(fish_count: usize) -> (App, Vec<Entity>) {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(HeadlessSimPlugin {
+            seed: Some(0),
+            ..default()
+        });
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let radius = (fish_count as f32 * AREA_PER_FISH / std::f32::consts::PI).sqrt();
+    let fishes = (0..fish_count)
+        .map(|_| {
+            let translation = radius * rng.gen::<f32>().sqrt() * random_direction(&mut rng);
+            app.world
+                .spawn(Fish::new_headless_npc(Transform::from_translation(
+                    translation,
+                )))
+                .id()
+        })
+        .collect();
+
+    for _ in 0..WARMUP_TICKS {
+        app.update();
+    }
+
+    (app, fishes)
+}
+
+fn bench_tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick");
+    group.sample_size(10);
+
+    for fish_count in FISH_COUNTS {
+        let (mut app, _) = pond(fish_count);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(fish_count),
+            &fish_count,
+            |b, _| b.iter(|| app.update()),
+        );
+    }
+
+    group.finish();
+}
+
+fn bench_system<Params>(
+    c: &mut Criterion,
+    name: &str,
+    system: impl IntoSystem<(), (), Params> + Copy,
+) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+
+    for fish_count in FISH_COUNTS {
+        let (mut app, _) = pond(fish_count);
+        let mut system = IntoSystem::into_system(system);
+        system.initialize(&mut app.world);
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(fish_count),
+            &fish_count,
+            |b, _| b.iter(|| system.run((), &mut app.world)),
+        );
+    }
+
+    group.finish();
+}
+
+fn bench_forces(c: &mut Criterion) {
+    bench_system(c, "cohesion_force_system", cohesion_force_system);
+    bench_system(c, "separation_force_system", separation_force_system);
+    bench_system(c, "alignment_force_system", alignment_force_system);
+}
+
+/// Feeds `group_by_proximity_system` one collision per neighbouring pair of fish,
+/// starting from an ungrouped pond each iteration.
+fn bench_grouping(c: &mut Criterion) {
+    let mut group = c.benchmark_group("group_by_proximity_system");
+    group.sample_size(10);
+
+    for fish_count in FISH_COUNTS {
+        let (mut app, fishes) = pond(fish_count);
+        let mut system = IntoSystem::into_system(group_by_proximity_system);
+        system.initialize(&mut app.world);
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(fish_count),
+            &fish_count,
+            |b, _| {
+                b.iter_custom(|iterations| {
+                    let mut total = Duration::ZERO;
+                    for _ in 0..iterations {
+                        for entity in fishes.iter() {
+                            app.world.get_mut::<Groupable>(*entity).unwrap().id = None;
+                        }
+
+                        let mut events = app.world.resource_mut::<Events<CollisionEvent>>();
+                        events.clear();
+                        for pair in fishes.windows(2) {
+                            events.send(CollisionEvent::Started(
+                                pair[0],
+                                pair[1],
+                                CollisionEventFlags::SENSOR,
+                            ));
+                        }
+
+                        let start = Instant::now();
+                        system.run((), &mut app.world);
+                        total += start.elapsed();
+                    }
+                    total
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_tick, bench_forces, bench_grouping);
+criterion_main!(benches);
@@ -0,0 +1,401 @@
+use std::collections::HashMap;
+
+use bevy::{animation::animation_player, prelude::*, transform::TransformSystem};
+
+use crate::{
+    fishes::{Fish, FishAssets},
+    forces::Moveable,
+    states::GameState,
+};
+
+pub struct FishAnimationPlugin;
+
+impl Plugin for FishAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SwimAnimationSettings>()
+            .init_resource::<SwimClips>()
+            .register_type::<SwimState>()
+            .add_system_set(
+                SystemSet::on_exit(GameState::AssetLoading).with_system(load_swim_clips),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Running)
+                    .with_system(link_animation_player_system)
+                    .with_system(swim_state_system)
+                    .with_system(
+                        fish_animator_system
+                            .after(link_animation_player_system)
+                            .after(swim_state_system),
+                    ),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                crossfade_system
+                    .after(animation_player)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// What a fish is doing, which picks the [`SwimGait`] it swims with.
+#[derive(Component, Reflect, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[reflect(Component)]
+pub enum SwimState {
+    Idle,
+    #[default]
+    Cruise,
+    Dart,
+    Turn,
+}
+
+/// How a fish swims in one [`SwimState`].
+#[derive(Debug, Clone)]
+pub struct SwimGait {
+    /// Clip to play instead of the model's own, e.g. `models/fish.glb#Animation1`.
+    /// Gaits sharing a clip blend by playback rate alone.
+    pub clip: Option<String>,
+    /// Playback rate relative to the speed-driven one.
+    pub rate: f32,
+}
+
+impl SwimGait {
+    /// The model's own clip played at `rate`.
+    pub fn rate(rate: f32) -> Self {
+        Self { clip: None, rate }
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct SwimAnimationSettings {
+    /// Gait of each state. States without one, or whose clip fails to load, play the
+    /// model's own clip.
+    pub gaits: Vec<(SwimState, SwimGait)>,
+    /// Seconds the previous clip takes to fade out, and the playback rate takes to
+    /// settle, after a state change.
+    pub crossfade_time: f32,
+    /// Fish slower than this idle.
+    pub idle_speed: f32,
+    /// Fish faster than this dart.
+    pub dart_speed: f32,
+    /// Fish speeding up faster than this dart, in units per second squared.
+    pub dart_acceleration: f32,
+    /// Fish changing heading faster than this turn, in radians per second.
+    pub turn_rate: f32,
+    /// Seconds a fish stays in a state before it can change again.
+    pub min_state_time: f32,
+}
+
+impl SwimAnimationSettings {
+    fn gait(&self, state: SwimState) -> Option<&SwimGait> {
+        self.gaits
+            .iter()
+            .find(|(gait_state, _)| *gait_state == state)
+            .map(|(_, gait)| gait)
+    }
+}
+
+impl Default for SwimAnimationSettings {
+    /// `fish.glb` has a single swim cycle, so every state plays it at its own rate.
+    fn default() -> Self {
+        Self {
+            gaits: vec![
+                (SwimState::Idle, SwimGait::rate(0.4)),
+                (SwimState::Cruise, SwimGait::rate(1.0)),
+                (SwimState::Dart, SwimGait::rate(2.0)),
+                (SwimState::Turn, SwimGait::rate(1.4)),
+            ],
+            crossfade_time: 0.3,
+            idle_speed: 1.0,
+            dart_speed: 18.0,
+            dart_acceleration: 30.0,
+            turn_rate: 2.0,
+            min_state_time: 0.25,
+        }
+    }
+}
+
+/// Loaded clips for each [`SwimState`] whose gait has its own.
+#[derive(Resource, Default)]
+pub struct SwimClips {
+    by_state: HashMap<SwimState, Handle<AnimationClip>>,
+}
+
+/// The entity holding a fish's [`AnimationPlayer`], somewhere inside its scene.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct FishAnimationLink(pub Entity);
+
+/// Tracks a fish's motion and the clip it is playing.
+#[derive(Component)]
+pub struct SwimAnimator {
+    state_time: f32,
+    heading: Vec3,
+    speed: f32,
+    playing: Handle<AnimationClip>,
+    /// Gait playback rate, easing toward the current state's.
+    rate: f32,
+    fade: Option<Crossfade>,
+}
+
+impl Default for SwimAnimator {
+    fn default() -> Self {
+        Self {
+            state_time: 0.0,
+            heading: Vec3::ZERO,
+            speed: 0.0,
+            playing: Handle::default(),
+            rate: 1.0,
+            fade: None,
+        }
+    }
+}
+
+/// A clip being faded out after the fish changed state.
+struct Crossfade {
+    clip: Handle<AnimationClip>,
+    elapsed: f32,
+    speed: f32,
+    progress: f32,
+}
+
+fn load_swim_clips(
+    settings: Res<SwimAnimationSettings>,
+    asset_server: Res<AssetServer>,
+    mut clips: ResMut<SwimClips>,
+) {
+    clips.by_state = settings
+        .gaits
+        .iter()
+        .filter_map(|(state, gait)| Some((*state, asset_server.load(gait.clip.as_deref()?))))
+        .collect();
+}
+
+/// Links each newly instanced animation player to the fish whose scene it belongs to,
+/// so the animator never has to search a fish's scene for it.
+pub fn link_animation_player_system(
+    mut commands: Commands,
+    players: Query<Entity, Added<AnimationPlayer>>,
+    parents: Query<&Parent>,
+    fishes: Query<(), With<Fish>>,
+) {
+    players.for_each(|player| {
+        if let Some(fish) = parents
+            .iter_ancestors(player)
+            .find(|ancestor| fishes.contains(*ancestor))
+        {
+            commands.entity(fish).insert((
+                FishAnimationLink(player),
+                SwimState::default(),
+                SwimAnimator::default(),
+            ));
+        }
+    });
+}
+
+fn swim_state_system(
+    time: Res<Time>,
+    settings: Res<SwimAnimationSettings>,
+    mut fishes: Query<(&Moveable, &mut SwimState, &mut SwimAnimator)>,
+) {
+    let delta = time.delta_seconds();
+    if delta <= 0.0 {
+        return;
+    }
+
+    fishes.for_each_mut(|(moveable, mut state, mut animator)| {
+        let speed = moveable.velocity.length();
+        let heading = moveable.velocity.normalize_or_zero();
+        let acceleration = (speed - animator.speed) / delta;
+        let turn_rate = if heading != Vec3::ZERO && animator.heading != Vec3::ZERO {
+            heading.angle_between(animator.heading) / delta
+        } else {
+            0.0
+        };
+        animator.speed = speed;
+        animator.heading = heading;
+        animator.state_time += delta;
+
+        let desired = if speed < settings.idle_speed {
+            SwimState::Idle
+        } else if speed > settings.dart_speed || acceleration > settings.dart_acceleration {
+            SwimState::Dart
+        } else if turn_rate > settings.turn_rate {
+            SwimState::Turn
+        } else {
+            SwimState::Cruise
+        };
+
+        if desired != *state && animator.state_time >= settings.min_state_time {
+            *state = desired;
+            animator.state_time = 0.0;
+        }
+    });
+}
+
+pub fn fish_animator_system(
+    time: Res<Time>,
+    settings: Res<SwimAnimationSettings>,
+    fish_assets: Res<FishAssets>,
+    clips: Res<SwimClips>,
+    animations: Res<Assets<AnimationClip>>,
+    mut fishes: Query<(&Moveable, &SwimState, &FishAnimationLink, &mut SwimAnimator)>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    let delta = time.delta_seconds();
+    fishes.for_each_mut(|(moveable, state, link, mut animator)| {
+        let mut player = match players.get_mut(link.0) {
+            Ok(player) => player,
+            Err(_) => return,
+        };
+
+        let clip = clips
+            .by_state
+            .get(state)
+            .filter(|clip| animations.contains(*clip))
+            .unwrap_or(&fish_assets.fish_animation);
+
+        if animator.playing != *clip {
+            if animations.contains(&animator.playing) {
+                animator.fade = Some(Crossfade {
+                    clip: animator.playing.clone(),
+                    elapsed: player.elapsed(),
+                    speed: player.speed(),
+                    progress: 0.0,
+                });
+            }
+            player.play(clip.clone()).repeat();
+            animator.playing = clip.clone();
+        }
+
+        let target_rate = settings.gait(*state).map_or(1.0, |gait| gait.rate);
+        animator.rate = if settings.crossfade_time > 0.0 {
+            let blend = (delta / settings.crossfade_time).min(1.0);
+            animator.rate + (target_rate - animator.rate) * blend
+        } else {
+            target_rate
+        };
+
+        let speed = moveable.velocity.length();
+        let animation_speed = (1.0 + speed) * animator.rate;
+        player.set_speed(animation_speed);
+    });
+}
+
+/// Blends the pose of the clip being faded out over the one `animation_player` just applied.
+fn crossfade_system(
+    time: Res<Time>,
+    settings: Res<SwimAnimationSettings>,
+    animations: Res<Assets<AnimationClip>>,
+    mut fishes: Query<(&FishAnimationLink, &mut SwimAnimator)>,
+    names: Query<&Name>,
+    children: Query<&Children>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let delta = time.delta_seconds();
+    fishes.for_each_mut(|(link, mut animator)| {
+        let fade = match animator.fade.as_mut() {
+            Some(fade) => fade,
+            None => return,
+        };
+
+        fade.progress += if settings.crossfade_time > 0.0 {
+            delta / settings.crossfade_time
+        } else {
+            1.0
+        };
+        let clip = match animations.get(&fade.clip) {
+            Some(clip) if fade.progress < 1.0 && clip.duration() > 0.0 => clip,
+            _ => {
+                animator.fade = None;
+                return;
+            }
+        };
+
+        fade.elapsed += delta * fade.speed;
+        let elapsed = fade.elapsed.rem_euclid(clip.duration());
+
+        for (path, curves) in clip.curves() {
+            let target = match find_target(link.0, path, &children, &names) {
+                Some(target) => target,
+                None => continue,
+            };
+            let mut transform = match transforms.get_mut(target) {
+                Ok(transform) => transform,
+                Err(_) => continue,
+            };
+
+            for curve in curves {
+                match sample_curve(curve, elapsed) {
+                    Some(Pose::Rotation(rotation)) => {
+                        transform.rotation = rotation.slerp(transform.rotation, fade.progress);
+                    }
+                    Some(Pose::Translation(translation)) => {
+                        transform.translation =
+                            translation.lerp(transform.translation, fade.progress);
+                    }
+                    Some(Pose::Scale(scale)) => {
+                        transform.scale = scale.lerp(transform.scale, fade.progress);
+                    }
+                    None => {}
+                }
+            }
+        }
+    });
+}
+
+/// Follows `path` down from the animation player's entity, as `animation_player` does.
+fn find_target(
+    root: Entity,
+    path: &EntityPath,
+    children: &Query<&Children>,
+    names: &Query<&Name>,
+) -> Option<Entity> {
+    // The first name is the root itself.
+    path.parts.iter().skip(1).try_fold(root, |current, part| {
+        children
+            .get(current)
+            .ok()?
+            .iter()
+            .copied()
+            .find(|child| names.get(*child).map_or(false, |name| name == part))
+    })
+}
+
+enum Pose {
+    Rotation(Quat),
+    Translation(Vec3),
+    Scale(Vec3),
+}
+
+/// The value of `curve` at `elapsed` seconds, or `None` outside of its keyframes.
+fn sample_curve(curve: &VariableCurve, elapsed: f32) -> Option<Pose> {
+    let timestamps = &curve.keyframe_timestamps;
+    if timestamps.len() == 1 {
+        return Some(match &curve.keyframes {
+            Keyframes::Rotation(keyframes) => Pose::Rotation(keyframes[0]),
+            Keyframes::Translation(keyframes) => Pose::Translation(keyframes[0]),
+            Keyframes::Scale(keyframes) => Pose::Scale(keyframes[0]),
+        });
+    }
+
+    let step = match timestamps.binary_search_by(|probe| probe.total_cmp(&elapsed)) {
+        Ok(i) if i < timestamps.len() - 1 => i,
+        Err(i) if i > 0 && i < timestamps.len() => i - 1,
+        _ => return None,
+    };
+    let lerp = (elapsed - timestamps[step]) / (timestamps[step + 1] - timestamps[step]);
+
+    Some(match &curve.keyframes {
+        Keyframes::Rotation(keyframes) => {
+            let start = keyframes[step].normalize();
+            let mut end = keyframes[step + 1].normalize();
+            if end.dot(start) < 0.0 {
+                end = -end;
+            }
+            Pose::Rotation(start.slerp(end, lerp))
+        }
+        Keyframes::Translation(keyframes) => {
+            Pose::Translation(keyframes[step].lerp(keyframes[step + 1], lerp))
+        }
+        Keyframes::Scale(keyframes) => Pose::Scale(keyframes[step].lerp(keyframes[step + 1], lerp)),
+    })
+}
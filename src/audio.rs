@@ -0,0 +1,601 @@
+use std::{
+    collections::HashMap,
+    f32::consts::FRAC_PI_4,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{
+    audio::{play_queued_audio_system, AudioOutput, Decodable, Sample, Source},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use bevy_asset_loader::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::TrackingCenterOfMassCamera,
+    groups::{Groupable, JoinedPlayerEvent},
+    levels::{LevelCompleteEvent, LevelOutcome},
+    lifecycle::FishBornEvent,
+    states::GameState,
+};
+
+const VOLUME_STEP: f32 = 0.1;
+
+pub struct AudioCuePlugin;
+
+impl Plugin for AudioCuePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AudioCueConfig>()
+            .init_resource::<AudioSettings>()
+            .init_resource::<AudioSettingsFile>()
+            .init_resource::<CueSounds>()
+            .init_resource::<MusicLayerSinks>()
+            .add_event::<PlayCueEvent>()
+            .add_asset::<PannedAudio>()
+            .init_resource::<Audio<PannedAudio>>()
+            .init_non_send_resource::<AudioOutput<PannedAudio>>()
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                play_queued_audio_system::<PannedAudio>,
+            )
+            .add_startup_system(load_audio_settings)
+            .add_system_set(
+                SystemSet::on_exit(GameState::AssetLoading).with_system(setup_audio_cues),
+            )
+            .add_system(audio_settings_input_system)
+            .add_system(fish_joined_player_cue_system.before(play_cue_system))
+            .add_system(fish_born_cue_system.before(play_cue_system))
+            .add_system(level_complete_cue_system.before(play_cue_system))
+            .add_system(play_cue_system)
+            .add_system(music_layers_system);
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct AudioAssets {
+    #[asset(path = "sounds/background.ogg")]
+    pub background_music: Handle<AudioSource>,
+    #[asset(path = "sounds/bubbles.ogg")]
+    pub bubbles_sfx: Handle<AudioSource>,
+}
+
+/// Something happening in the pond that can be heard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AudioCue {
+    FishJoined,
+    FishBorn,
+    LevelWon,
+    LevelLost,
+}
+
+/// Asks for a cue to be played, panned toward `position` if it has one.
+pub struct PlayCueEvent {
+    pub cue: AudioCue,
+    pub position: Option<Vec3>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CueSound {
+    /// Sound file, relative to the assets directory.
+    pub path: String,
+    pub volume: f32,
+    /// Playback speed, which also shifts the pitch.
+    pub pitch: f32,
+    /// Largest fraction the volume varies by either way on each play.
+    pub volume_variation: f32,
+    /// Largest fraction the pitch varies by either way on each play.
+    pub pitch_variation: f32,
+    /// Seconds before the cue can play again; cues requested sooner are dropped.
+    pub cooldown: f32,
+}
+
+/// A looping track that fades in as the player's school grows.
+#[derive(Debug, Clone)]
+pub struct MusicLayer {
+    /// Sound file, relative to the assets directory.
+    pub path: String,
+    pub volume: f32,
+    /// School size at which the layer starts fading in.
+    pub fade_in_from: usize,
+    /// School size at which the layer plays at full volume.
+    pub full_at: usize,
+}
+
+impl MusicLayer {
+    /// Volume of the layer for a school of `school_size` fish.
+    pub fn volume_for(&self, school_size: usize) -> f32 {
+        if school_size >= self.full_at {
+            return self.volume;
+        }
+        if school_size <= self.fade_in_from {
+            return 0.0;
+        }
+
+        let progress =
+            (school_size - self.fade_in_from) as f32 / (self.full_at - self.fade_in_from) as f32;
+        self.volume * progress
+    }
+}
+
+/// Which sound each cue plays and how the music is layered.
+#[derive(Resource, Debug, Clone)]
+pub struct AudioCueConfig {
+    /// Cues without a sound are silent.
+    pub cues: HashMap<AudioCue, CueSound>,
+    pub music_layers: Vec<MusicLayer>,
+    /// Seconds music layers take to settle at a new volume.
+    pub music_fade_time: f32,
+    /// How far cues pan toward the side of the screen they happen on, from 0 to 1.
+    pub pan_strength: f32,
+}
+
+impl Default for AudioCueConfig {
+    fn default() -> Self {
+        Self {
+            cues: HashMap::from([
+                (
+                    AudioCue::FishJoined,
+                    CueSound {
+                        path: "sounds/bubbles.ogg".to_string(),
+                        volume: 1.0,
+                        pitch: 1.0,
+                        volume_variation: 0.2,
+                        pitch_variation: 0.15,
+                        cooldown: 0.15,
+                    },
+                ),
+                (
+                    AudioCue::FishBorn,
+                    CueSound {
+                        path: "sounds/bubbles.ogg".to_string(),
+                        volume: 0.6,
+                        pitch: 1.5,
+                        volume_variation: 0.1,
+                        pitch_variation: 0.1,
+                        cooldown: 0.5,
+                    },
+                ),
+                (
+                    AudioCue::LevelWon,
+                    CueSound {
+                        path: "sounds/bubbles.ogg".to_string(),
+                        volume: 1.0,
+                        pitch: 2.0,
+                        volume_variation: 0.0,
+                        pitch_variation: 0.0,
+                        cooldown: 1.0,
+                    },
+                ),
+                (
+                    AudioCue::LevelLost,
+                    CueSound {
+                        path: "sounds/bubbles.ogg".to_string(),
+                        volume: 1.0,
+                        pitch: 0.6,
+                        volume_variation: 0.0,
+                        pitch_variation: 0.0,
+                        cooldown: 1.0,
+                    },
+                ),
+            ]),
+            music_layers: vec![
+                MusicLayer {
+                    path: "sounds/background.ogg".to_string(),
+                    volume: 1.0,
+                    fade_in_from: 0,
+                    full_at: 0,
+                },
+                MusicLayer {
+                    path: "sounds/bubbles.ogg".to_string(),
+                    volume: 0.4,
+                    fade_in_from: 5,
+                    full_at: 20,
+                },
+            ],
+            music_fade_time: 2.0,
+            pan_strength: 0.8,
+        }
+    }
+}
+
+/// Player volume preferences, saved to [`AudioSettingsFile`] whenever they change.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    /// Multiplier for music volumes.
+    pub fn music_gain(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.master_volume * self.music_volume
+        }
+    }
+
+    /// Multiplier for sound effect volumes.
+    pub fn sfx_gain(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.master_volume * self.sfx_volume
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, contents)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        ron::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[derive(Resource)]
+pub struct AudioSettingsFile {
+    pub path: PathBuf,
+}
+
+impl Default for AudioSettingsFile {
+    /// `audio_settings.ron` in the user's config directory, or the working directory
+    /// if there is none.
+    fn default() -> Self {
+        let dir = dirs::config_dir()
+            .map(|dir| dir.join("homekoi"))
+            .unwrap_or_default();
+        Self {
+            path: dir.join("audio_settings.ron"),
+        }
+    }
+}
+
+/// Loaded cue sounds and when each cue last played.
+#[derive(Resource, Default)]
+struct CueSounds {
+    by_cue: HashMap<AudioCue, Handle<AudioSource>>,
+    last_played: HashMap<AudioCue, Duration>,
+}
+
+/// Playing music layers, in [`AudioCueConfig::music_layers`] order, with their current volume.
+#[derive(Resource, Default)]
+struct MusicLayerSinks {
+    layers: Vec<(Handle<AudioSink>, f32)>,
+}
+
+/// A sound panned between the left and right speakers, from -1 to 1.
+#[derive(TypeUuid, Clone)]
+#[uuid = "0c1c5f4e-3a4d-4c55-9a8e-6c2f0d3b7e21"]
+pub struct PannedAudio {
+    pub source: AudioSource,
+    pub pan: f32,
+}
+
+impl Decodable for PannedAudio {
+    type Decoder = Panned<<AudioSource as Decodable>::Decoder>;
+    type DecoderItem = <AudioSource as Decodable>::DecoderItem;
+
+    fn decoder(&self) -> Self::Decoder {
+        Panned::new(self.source.decoder(), self.pan)
+    }
+}
+
+/// Applies equal-power panning to a source, spreading mono sources over two channels.
+pub struct Panned<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    input: S,
+    gains: [f32; 2],
+    channel: u16,
+    /// Right channel copy of the last mono sample.
+    pending: Option<S::Item>,
+}
+
+impl<S> Panned<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    pub fn new(input: S, pan: f32) -> Self {
+        let angle = (pan.clamp(-1.0, 1.0) + 1.0) * FRAC_PI_4;
+        Self {
+            input,
+            gains: [angle.cos(), angle.sin()],
+            channel: 0,
+            pending: None,
+        }
+    }
+}
+
+impl<S> Iterator for Panned<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        if let Some(sample) = self.pending.take() {
+            return Some(sample);
+        }
+
+        let sample = self.input.next()?;
+        let channels = self.input.channels();
+        if channels == 1 {
+            self.pending = Some(sample.amplify(self.gains[1]));
+            return Some(sample.amplify(self.gains[0]));
+        }
+
+        let gain = self
+            .gains
+            .get(self.channel as usize)
+            .copied()
+            .unwrap_or(1.0);
+        self.channel = (self.channel + 1) % channels;
+        Some(sample.amplify(gain))
+    }
+}
+
+impl<S> Source for Panned<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        let len = self.input.current_frame_len()?;
+        Some(if self.input.channels() == 1 {
+            2 * len
+        } else {
+            len
+        })
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels().max(2)
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+fn load_audio_settings(mut commands: Commands, file: Res<AudioSettingsFile>) {
+    match AudioSettings::load(&file.path) {
+        Ok(settings) => commands.insert_resource(settings),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => error!(
+            "Failed to load audio settings from {}: {}",
+            file.path.display(),
+            e
+        ),
+    }
+}
+
+fn audio_settings_input_system(
+    keys: Res<Input<KeyCode>>,
+    file: Res<AudioSettingsFile>,
+    mut settings: ResMut<AudioSettings>,
+) {
+    // Volumes are only snapped to the step when they're changed, so hand-edited
+    // settings survive until then.
+    let step = |volume: f32, down: KeyCode, up: KeyCode| {
+        let change = match (keys.just_pressed(down), keys.just_pressed(up)) {
+            (true, false) => -VOLUME_STEP,
+            (false, true) => VOLUME_STEP,
+            _ => return volume,
+        };
+        (((volume + change) / VOLUME_STEP).round() * VOLUME_STEP).clamp(0.0, 1.0)
+    };
+
+    let updated = AudioSettings {
+        master_volume: step(settings.master_volume, KeyCode::Minus, KeyCode::Equals),
+        music_volume: step(settings.music_volume, KeyCode::LBracket, KeyCode::RBracket),
+        sfx_volume: step(settings.sfx_volume, KeyCode::Semicolon, KeyCode::Apostrophe),
+        muted: settings.muted != keys.just_pressed(KeyCode::M),
+    };
+
+    if updated != *settings {
+        *settings = updated;
+        if let Err(e) = settings.save(&file.path) {
+            error!(
+                "Failed to save audio settings to {}: {}",
+                file.path.display(),
+                e
+            );
+        }
+    }
+}
+
+fn setup_audio_cues(
+    config: Res<AudioCueConfig>,
+    settings: Res<AudioSettings>,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    mut cue_sounds: ResMut<CueSounds>,
+    mut music: ResMut<MusicLayerSinks>,
+) {
+    cue_sounds.by_cue = config
+        .cues
+        .iter()
+        .map(|(cue, sound)| (*cue, asset_server.load(sound.path.as_str())))
+        .collect();
+
+    music.layers = config
+        .music_layers
+        .iter()
+        .map(|layer| {
+            let volume = layer.volume_for(0);
+            let sink = audio.play_with_settings(
+                asset_server.load(layer.path.as_str()),
+                PlaybackSettings {
+                    repeat: true,
+                    volume: volume * settings.music_gain(),
+                    ..default()
+                },
+            );
+            (audio_sinks.get_handle(sink), volume)
+        })
+        .collect();
+}
+
+pub fn fish_joined_player_cue_system(
+    mut ev_joined: EventReader<JoinedPlayerEvent>,
+    mut ev_cue: EventWriter<PlayCueEvent>,
+    transforms: Query<&GlobalTransform>,
+) {
+    for JoinedPlayerEvent(fish) in ev_joined.iter() {
+        ev_cue.send(PlayCueEvent {
+            cue: AudioCue::FishJoined,
+            position: transforms.get(*fish).ok().map(GlobalTransform::translation),
+        });
+    }
+}
+
+fn fish_born_cue_system(
+    mut ev_born: EventReader<FishBornEvent>,
+    mut ev_cue: EventWriter<PlayCueEvent>,
+    transforms: Query<&GlobalTransform>,
+) {
+    for born in ev_born.iter() {
+        ev_cue.send(PlayCueEvent {
+            cue: AudioCue::FishBorn,
+            position: transforms
+                .get(born.parents[0])
+                .ok()
+                .map(GlobalTransform::translation),
+        });
+    }
+}
+
+fn level_complete_cue_system(
+    mut ev_complete: EventReader<LevelCompleteEvent>,
+    mut ev_cue: EventWriter<PlayCueEvent>,
+) {
+    for LevelCompleteEvent(outcome) in ev_complete.iter() {
+        let cue = match outcome {
+            LevelOutcome::Won => AudioCue::LevelWon,
+            LevelOutcome::Lost(_) => AudioCue::LevelLost,
+        };
+        ev_cue.send(PlayCueEvent {
+            cue,
+            position: None,
+        });
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn play_cue_system(
+    time: Res<Time>,
+    config: Res<AudioCueConfig>,
+    settings: Res<AudioSettings>,
+    mut cue_sounds: ResMut<CueSounds>,
+    mut ev_cue: EventReader<PlayCueEvent>,
+    audio_sources: Res<Assets<AudioSource>>,
+    mut panned_sources: ResMut<Assets<PannedAudio>>,
+    panned_audio: Res<Audio<PannedAudio>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<TrackingCenterOfMassCamera>>,
+) {
+    let now = time.elapsed();
+    for event in ev_cue.iter() {
+        let sound = match config.cues.get(&event.cue) {
+            Some(sound) => sound,
+            None => continue,
+        };
+        if let Some(last_played) = cue_sounds.last_played.get(&event.cue) {
+            if now.saturating_sub(*last_played).as_secs_f32() < sound.cooldown {
+                continue;
+            }
+        }
+        let source = match cue_sounds
+            .by_cue
+            .get(&event.cue)
+            .and_then(|handle| audio_sources.get(handle))
+        {
+            Some(source) => source.clone(),
+            None => continue,
+        };
+        cue_sounds.last_played.insert(event.cue, now);
+
+        // Cues pan toward the side of the screen they happen on.
+        let pan = event
+            .position
+            .zip(cameras.get_single().ok())
+            .and_then(|(position, (camera, transform))| camera.world_to_ndc(transform, position))
+            .map_or(0.0, |ndc| config.pan_strength * ndc.x.clamp(-1.0, 1.0));
+
+        let mut rng = rand::thread_rng();
+        let mut vary = |value: f32, variation: f32| {
+            let change: f32 = rng.gen_range(-1.0..=1.0);
+            value * (1.0 + variation * change)
+        };
+
+        panned_audio.play_with_settings(
+            panned_sources.add(PannedAudio { source, pan }),
+            PlaybackSettings {
+                repeat: false,
+                volume: settings.sfx_gain() * vary(sound.volume, sound.volume_variation),
+                speed: vary(sound.pitch, sound.pitch_variation),
+            },
+        );
+    }
+}
+
+fn music_layers_system(
+    time: Res<Time>,
+    config: Res<AudioCueConfig>,
+    settings: Res<AudioSettings>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    mut music: ResMut<MusicLayerSinks>,
+    groupables: Query<&Groupable>,
+) {
+    let school_size = groupables
+        .iter()
+        .filter(|groupable| groupable.is_grouped_with_player())
+        .count();
+
+    let blend = if config.music_fade_time > 0.0 {
+        1.0 - (-time.delta_seconds() / config.music_fade_time).exp()
+    } else {
+        1.0
+    };
+
+    for (layer, (sink, volume)) in config.music_layers.iter().zip(music.layers.iter_mut()) {
+        let target = layer.volume_for(school_size);
+        *volume += blend * (target - *volume);
+        if let Some(sink) = audio_sinks.get(sink) {
+            sink.set_volume(*volume * settings.music_gain());
+        }
+    }
+}
@@ -1,63 +1,262 @@
 use bevy::prelude::*;
 
-const CAMERA_TRACKING_DISTANCE_SCALE: f32 = 2.44948974278;
-const CAMERA_MIN_DISTANCE: f32 = 50.0;
-const CAMERA_TRACKING_ZOOM: f32 = 1.0;
-const CAMERA_TRACKING_DELAY_LERP: f32 = 0.5;
+use crate::states::GameState;
 
-#[derive(Component, Default)]
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraTrackingSettings>()
+            .register_type::<TrackingCenterOfMassCamera>()
+            .register_type::<CameraTarget>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Running)
+                    .with_system(camera_center_of_mass_track_system)
+                    .with_system(temporary_camera_target_system),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct CameraTrackingSettings {
+    /// Time in seconds for the camera to close ~63% of the gap to its target.
+    pub smoothing_time: f32,
+    /// Distance the center of mass may drift before the camera pans after it.
+    pub dead_zone: f32,
+    /// Fraction of each half-screen kept clear around the tracked entities.
+    pub margin: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+}
+
+impl Default for CameraTrackingSettings {
+    fn default() -> Self {
+        Self {
+            smoothing_time: 0.1,
+            dead_zone: 2.0,
+            margin: 0.2,
+            min_distance: 50.0,
+            max_distance: 1000.0,
+        }
+    }
+}
+
+/// A [`CameraTarget`] that an event adds for a few seconds, as a
+/// [`TemporaryCameraTarget`].
+#[derive(Debug, Clone, Copy)]
+pub struct CameraFocus {
+    pub target: CameraTarget,
+    pub seconds: f32,
+}
+
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 pub struct TrackingCenterOfMassCamera;
 
-#[derive(Component, Default)]
-pub struct Tracked;
+/// Something the tracking camera keeps in frame.
+///
+/// `weight` is the entity's share of the point the camera centers on; a weight of
+/// zero only affects zoom. `padding` is extra room kept around the entity in world
+/// units. When every target cannot fit within `max_distance`, the lowest `priority`
+/// targets are dropped first.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct CameraTarget {
+    pub weight: f32,
+    pub padding: f32,
+    pub priority: i32,
+}
+
+impl Default for CameraTarget {
+    fn default() -> Self {
+        Self {
+            weight: 1.0,
+            padding: 0.0,
+            priority: 0,
+        }
+    }
+}
+
+impl CameraTarget {
+    pub fn leader() -> Self {
+        Self {
+            weight: 1.0,
+            padding: 5.0,
+            priority: 1,
+        }
+    }
+
+    pub fn follower() -> Self {
+        Self {
+            weight: 0.2,
+            padding: 0.0,
+            priority: 0,
+        }
+    }
+}
+
+/// Pulls the camera towards an entity until the timer finishes, for events that
+/// should only hold the camera's attention for a while. While it lasts it is used
+/// instead of the entity's own [`CameraTarget`], which is left untouched.
+#[derive(Component)]
+pub struct TemporaryCameraTarget {
+    pub target: CameraTarget,
+    pub timer: Timer,
+}
+
+impl TemporaryCameraTarget {
+    pub fn new(target: CameraTarget, seconds: f32) -> Self {
+        Self {
+            target,
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        }
+    }
+}
 
-#[derive(Component, Default)]
-pub struct TrackedZoomOnly;
+impl From<CameraFocus> for TemporaryCameraTarget {
+    fn from(focus: CameraFocus) -> Self {
+        Self::new(focus.target, focus.seconds)
+    }
+}
 
 pub fn camera_center_of_mass_track_system(
-    mut camera: Query<(&Camera, &mut Transform, With<TrackingCenterOfMassCamera>)>,
-    trackables: Query<(&Transform, With<Tracked>, Without<Camera>)>,
-    zoom_trackables: Query<(
-        &Transform,
-        (With<TrackedZoomOnly>, Without<Camera>, Without<Tracked>),
+    time: Res<Time>,
+    settings: Res<CameraTrackingSettings>,
+    mut camera: Query<(
+        &Camera,
+        &Projection,
+        &mut Transform,
+        With<TrackingCenterOfMassCamera>,
     )>,
+    targets: Query<
+        (
+            &Transform,
+            Option<&CameraTarget>,
+            Option<&TemporaryCameraTarget>,
+        ),
+        (
+            Or<(With<CameraTarget>, With<TemporaryCameraTarget>)>,
+            Without<Camera>,
+        ),
+    >,
 ) {
-    let positions_summed: Vec3 = trackables
-        .iter()
-        .map(|(transform, _, _)| transform.translation)
-        .sum();
-
-    let count = trackables.iter().count();
-    let center_of_mass = (1.0 / count as f32) * positions_summed;
-
-    let trackables_furthest_distance_squared = trackables
-        .iter()
-        .map(|(transform, _, _)| transform.translation.distance_squared(center_of_mass))
-        .fold(0.0_f32, |d1, d2| d1.max(d2));
-
-    let zoom_trackables_furthest_distance_squared = zoom_trackables
-        .iter()
-        .map(|(transform, _)| transform.translation.distance_squared(center_of_mass))
-        .fold(0.0_f32, |d1, d2| d1.max(d2));
-
-    let furthest_distance_squared = f32::max(
-        trackables_furthest_distance_squared,
-        zoom_trackables_furthest_distance_squared,
-    );
-
-    let furthest_distance = furthest_distance_squared.sqrt();
-
-    if let Ok((_, mut transform, _)) = camera.get_single_mut() {
-        let target = Vec3::new(
-            center_of_mass.x,
-            center_of_mass.y,
-            f32::max(
-                CAMERA_TRACKING_ZOOM * CAMERA_TRACKING_DISTANCE_SCALE * furthest_distance,
-                CAMERA_MIN_DISTANCE,
-            ),
-        );
-        transform.translation = transform
-            .translation
-            .lerp(target, CAMERA_TRACKING_DELAY_LERP);
+    if let Ok((_, projection, mut transform, _)) = camera.get_single_mut() {
+        let mut targets: Vec<(Vec3, CameraTarget)> = targets
+            .iter()
+            .filter_map(|(transform, target, temporary)| {
+                let target = temporary.map(|temporary| &temporary.target).or(target)?;
+                Some((transform.translation, *target))
+            })
+            .collect();
+        targets.sort_by_key(|(_, target)| std::cmp::Reverse(target.priority));
+
+        let current = transform.translation;
+        let mut framed: Option<(Vec3, f32)> = None;
+        let mut start = 0;
+
+        while start < targets.len() {
+            let priority = targets[start].1.priority;
+            let end = targets[start..]
+                .iter()
+                .position(|(_, target)| target.priority != priority)
+                .map_or(targets.len(), |offset| start + offset);
+
+            if let Some(candidate) = frame_targets(&targets[..end], current, projection, &settings)
+            {
+                if framed.is_some() && candidate.1 > settings.max_distance {
+                    break;
+                }
+                framed = Some(candidate);
+            }
+
+            start = end;
+        }
+
+        let (mut target, distance) = match framed {
+            Some(framed) => framed,
+            None => return,
+        };
+        target.z = distance.clamp(settings.min_distance, settings.max_distance);
+
+        let t = if settings.smoothing_time > f32::EPSILON {
+            1.0 - (-time.delta_seconds() / settings.smoothing_time).exp()
+        } else {
+            1.0
+        };
+
+        transform.translation = current.lerp(target, t);
     }
 }
+
+pub fn temporary_camera_target_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut temporaries: Query<(Entity, &mut TemporaryCameraTarget)>,
+) {
+    temporaries.for_each_mut(|(entity, mut temporary)| {
+        if temporary.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<TemporaryCameraTarget>();
+        }
+    });
+}
+
+/// Camera position for `targets` (with the dead-zone applied) and the unclamped
+/// distance needed to fit them, or `None` if they carry no weight.
+fn frame_targets(
+    targets: &[(Vec3, CameraTarget)],
+    current: Vec3,
+    projection: &Projection,
+    settings: &CameraTrackingSettings,
+) -> Option<(Vec3, f32)> {
+    let (positions_summed, weight_summed) =
+        targets
+            .iter()
+            .fold((Vec3::ZERO, 0.0), |(sum, weight), (translation, target)| {
+                (sum + target.weight * *translation, weight + target.weight)
+            });
+
+    if weight_summed <= f32::EPSILON {
+        return None;
+    }
+
+    let center_of_mass = (1.0 / weight_summed) * positions_summed;
+
+    let mut position = current;
+    let pan_delta = center_of_mass.truncate() - current.truncate();
+    let pan_distance = pan_delta.length();
+    if pan_distance > settings.dead_zone {
+        let pan = pan_delta * ((pan_distance - settings.dead_zone) / pan_distance);
+        position.x += pan.x;
+        position.y += pan.y;
+    }
+
+    let distance = match projection {
+        Projection::Perspective(perspective) => {
+            let extents = targets
+                .iter()
+                .map(|(translation, target)| {
+                    (translation.truncate() - position.truncate()).abs()
+                        + Vec2::splat(target.padding)
+                })
+                .fold(Vec2::ZERO, Vec2::max);
+
+            fit_distance(perspective, extents, settings.margin)
+        }
+        Projection::Orthographic(_) => current.z,
+    };
+
+    Some((position, distance))
+}
+
+/// Distance from the z = 0 plane at which `extents` (half-width, half-height) fit
+/// inside the view frustum, leaving `margin` of each half-screen free.
+fn fit_distance(perspective: &PerspectiveProjection, extents: Vec2, margin: f32) -> f32 {
+    let tan_half_vertical = (0.5 * perspective.fov).tan();
+    let tan_half_horizontal = tan_half_vertical * perspective.aspect_ratio;
+    let usable = (1.0 - margin).max(f32::EPSILON);
+
+    f32::max(
+        extents.x / tan_half_horizontal,
+        extents.y / tan_half_vertical,
+    ) / usable
+}
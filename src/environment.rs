@@ -0,0 +1,414 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::VertexAttributeValues,
+        render_resource::{
+            AddressMode, Extent3d, SamplerDescriptor, TextureDimension, TextureFormat,
+        },
+        texture::ImageSampler,
+    },
+};
+use rand::Rng;
+
+use crate::{
+    fishes::Fish,
+    input::try_get_cursor_position_on_z_plane,
+    levels::{Level, LevelAssets, PondShape},
+    states::{GameState, Session},
+};
+
+const CAUSTICS_TEXTURE_SIZE: u32 = 128;
+/// World units covered by one repeat of the caustics texture.
+const CAUSTICS_TILE_SIZE: f32 = 60.0;
+const RIPPLE_SEGMENTS: usize = 32;
+
+pub struct PondEnvironmentPlugin;
+
+impl Plugin for PondEnvironmentPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PondEnvironmentSettings>()
+            .add_event::<RippleEvent>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Running).with_system(spawn_pond_environment),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Running)
+                    .with_system(click_ripple_system)
+                    .with_system(fish_surface_ripple_system)
+                    .with_system(spawn_ripple_system)
+                    .with_system(ripple_system)
+                    .with_system(caustics_system),
+            );
+    }
+}
+
+/// What parts of the pond environment are drawn. Everything can be turned off
+/// for low-end machines and the web build.
+#[derive(Resource, Debug, Clone)]
+pub struct PondEnvironmentSettings {
+    pub water_surface: bool,
+    pub floor: bool,
+    pub ripples: bool,
+    pub caustics: bool,
+    /// Height of the water surface above the fish.
+    pub surface_height: f32,
+    /// Depth of the pond floor below the fish.
+    pub floor_depth: f32,
+    pub water_color: Color,
+    pub floor_color: Color,
+    /// Seconds a ripple takes to spread out and fade.
+    pub ripple_lifetime: f32,
+    pub ripple_radius: f32,
+    /// New ripples are dropped while this many are spreading.
+    pub max_ripples: usize,
+    /// Chance per second of each fish surfacing and leaving a ripple.
+    pub surface_chance: f32,
+    /// Times per second the caustics pattern is redrawn.
+    pub caustics_rate: f32,
+}
+
+impl Default for PondEnvironmentSettings {
+    fn default() -> Self {
+        if cfg!(target_arch = "wasm32") {
+            Self::low_end()
+        } else {
+            Self::full()
+        }
+    }
+}
+
+impl PondEnvironmentSettings {
+    /// Only the water surface and floor, without any per-frame work.
+    pub fn low_end() -> Self {
+        Self {
+            ripples: false,
+            caustics: false,
+            ..Self::full()
+        }
+    }
+
+    /// Nothing but the clear colour, as before the pond environment existed.
+    pub fn disabled() -> Self {
+        Self {
+            water_surface: false,
+            floor: false,
+            ..Self::low_end()
+        }
+    }
+
+    /// Everything turned on.
+    pub fn full() -> Self {
+        Self {
+            water_surface: true,
+            floor: true,
+            ripples: true,
+            caustics: true,
+            surface_height: 8.0,
+            floor_depth: 20.0,
+            water_color: Color::rgba(0.2, 0.45, 0.55, 0.25),
+            floor_color: Color::rgb(0.45, 0.4, 0.3),
+            ripple_lifetime: 1.5,
+            ripple_radius: 8.0,
+            max_ripples: 32,
+            surface_chance: 0.01,
+            caustics_rate: 15.0,
+        }
+    }
+}
+
+/// Starts a ripple on the water surface above this point of the pond.
+pub struct RippleEvent(pub Vec2);
+
+#[derive(Component)]
+struct Ripple {
+    age: f32,
+}
+
+/// The texture drawn over the pond floor, redrawn as time passes.
+#[derive(Component)]
+struct Caustics {
+    image: Handle<Image>,
+    since_redraw: f32,
+}
+
+fn spawn_pond_environment(
+    mut commands: Commands,
+    settings: Res<PondEnvironmentSettings>,
+    level_assets: Option<Res<LevelAssets>>,
+    levels: Option<Res<Assets<Level>>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let pond = match level_assets
+        .zip(levels)
+        .and_then(|(level_assets, levels)| levels.get(&level_assets.level).map(|level| level.pond))
+    {
+        Some(pond) => pond,
+        None => return,
+    };
+
+    if settings.floor {
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(pond_mesh(pond, None)),
+                material: materials.add(StandardMaterial {
+                    base_color: settings.floor_color,
+                    perceptual_roughness: 1.0,
+                    ..default()
+                }),
+                transform: Transform::from_xyz(0.0, 0.0, -settings.floor_depth),
+                ..default()
+            },
+            Session,
+        ));
+    }
+
+    if settings.caustics {
+        let image = images.add(caustics_image(0.0));
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(pond_mesh(pond, Some(CAUSTICS_TILE_SIZE))),
+                material: materials.add(StandardMaterial {
+                    base_color_texture: Some(image.clone()),
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    ..default()
+                }),
+                // Just above the floor so the two never fight over depth.
+                transform: Transform::from_xyz(0.0, 0.0, 0.1 - settings.floor_depth),
+                ..default()
+            },
+            Caustics {
+                image,
+                since_redraw: 0.0,
+            },
+            Session,
+        ));
+    }
+
+    if settings.water_surface {
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(pond_mesh(pond, None)),
+                material: materials.add(StandardMaterial {
+                    base_color: settings.water_color,
+                    alpha_mode: AlphaMode::Blend,
+                    perceptual_roughness: 0.1,
+                    reflectance: 0.8,
+                    ..default()
+                }),
+                transform: Transform::from_xyz(0.0, 0.0, settings.surface_height),
+                ..default()
+            },
+            Session,
+        ));
+    }
+}
+
+/// A flat mesh the shape of the pond, facing the camera. With a `tile_size`, its UVs
+/// repeat every `tile_size` world units.
+fn pond_mesh(pond: PondShape, tile_size: Option<f32>) -> Mesh {
+    let (mut mesh, size) = match pond {
+        PondShape::Circle { radius } => (
+            Mesh::from(shape::Circle {
+                radius,
+                vertices: 64,
+            }),
+            Vec2::splat(2.0 * radius),
+        ),
+        PondShape::Rectangle {
+            half_width,
+            half_height,
+        } => {
+            let size = 2.0 * Vec2::new(half_width, half_height);
+            (Mesh::from(shape::Quad::new(size)), size)
+        }
+    };
+
+    let uvs = mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0);
+    if let (Some(tile_size), Some(VertexAttributeValues::Float32x2(uvs))) = (tile_size, uvs) {
+        let repeats = size / tile_size;
+        for uv in uvs.iter_mut() {
+            uv[0] *= repeats.x;
+            uv[1] *= repeats.y;
+        }
+    }
+
+    mesh
+}
+
+/// A repeating caustics pattern at `time`: bright where several moving waves line up.
+fn caustics_image(time: f32) -> Image {
+    let size = CAUSTICS_TEXTURE_SIZE;
+    let mut data = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let u = TAU * x as f32 / size as f32;
+            let v = TAU * y as f32 / size as f32;
+            let waves = (2.0 * u + time).sin()
+                + (3.0 * v - 1.3 * time).sin()
+                + (2.0 * (u + v) + 0.7 * time).sin()
+                + (3.0 * (u - v) - 0.9 * time).sin();
+            let brightness = (waves / 4.0 + 0.5).clamp(0.0, 1.0).powi(6);
+            let alpha = (255.0 * 0.6 * brightness) as u8;
+            data.extend_from_slice(&[255, 255, 240, alpha]);
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    image.sampler_descriptor = ImageSampler::Descriptor(SamplerDescriptor {
+        address_mode_u: AddressMode::Repeat,
+        address_mode_v: AddressMode::Repeat,
+        ..ImageSampler::linear_descriptor()
+    });
+    image
+}
+
+fn caustics_system(
+    time: Res<Time>,
+    settings: Res<PondEnvironmentSettings>,
+    mut images: ResMut<Assets<Image>>,
+    mut caustics: Query<&mut Caustics>,
+) {
+    if settings.caustics_rate <= 0.0 {
+        return;
+    }
+
+    caustics.for_each_mut(|mut caustics| {
+        caustics.since_redraw += time.delta_seconds();
+        if caustics.since_redraw < 1.0 / settings.caustics_rate {
+            return;
+        }
+        caustics.since_redraw = 0.0;
+
+        if let Some(image) = images.get_mut(&caustics.image) {
+            image.data = caustics_image(time.elapsed_seconds()).data;
+        }
+    });
+}
+
+fn click_ripple_system(
+    windows: Res<Windows>,
+    buttons: Res<Input<MouseButton>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut ev_ripple: EventWriter<RippleEvent>,
+) {
+    if buttons.just_pressed(MouseButton::Left) {
+        if let Some(position) = try_get_cursor_position_on_z_plane(windows, cameras) {
+            ev_ripple.send(RippleEvent(position.truncate()));
+        }
+    }
+}
+
+fn fish_surface_ripple_system(
+    time: Res<Time>,
+    settings: Res<PondEnvironmentSettings>,
+    fishes: Query<&Transform, With<Fish>>,
+    mut ev_ripple: EventWriter<RippleEvent>,
+) {
+    if !settings.ripples || settings.surface_chance <= 0.0 {
+        return;
+    }
+
+    // Ripples are only for show, so they don't draw from the pond's seeded RNG.
+    let mut rng = rand::thread_rng();
+    let chance = settings.surface_chance * time.delta_seconds();
+    fishes.for_each(|transform| {
+        if rng.gen::<f32>() < chance {
+            ev_ripple.send(RippleEvent(transform.translation.truncate()));
+        }
+    });
+}
+
+fn spawn_ripple_system(
+    mut commands: Commands,
+    settings: Res<PondEnvironmentSettings>,
+    mut ev_ripple: EventReader<RippleEvent>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut ripple_mesh: Local<Option<Handle<Mesh>>>,
+    ripples: Query<(), With<Ripple>>,
+) {
+    if !settings.ripples {
+        ev_ripple.clear();
+        return;
+    }
+
+    let mut count = ripples.iter().count();
+    for RippleEvent(position) in ev_ripple.iter() {
+        if count >= settings.max_ripples {
+            break;
+        }
+        count += 1;
+
+        let mesh = ripple_mesh
+            .get_or_insert_with(|| {
+                meshes.add(Mesh::from(shape::Torus {
+                    radius: 1.0,
+                    ring_radius: 0.05,
+                    subdivisions_segments: RIPPLE_SEGMENTS,
+                    subdivisions_sides: 4,
+                }))
+            })
+            .clone();
+
+        commands.spawn((
+            PbrBundle {
+                mesh,
+                // Each ripple fades on its own, so it needs its own material.
+                material: materials.add(StandardMaterial {
+                    base_color: Color::rgba(1.0, 1.0, 1.0, 0.6),
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    ..default()
+                }),
+                // The torus lies flat in the XZ plane; stand it up to face the camera.
+                transform: Transform::from_translation(position.extend(settings.surface_height))
+                    .with_rotation(Quat::from_rotation_x(TAU / 4.0))
+                    .with_scale(Vec3::ZERO),
+                ..default()
+            },
+            Ripple { age: 0.0 },
+            Session,
+        ));
+    }
+}
+
+fn ripple_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<PondEnvironmentSettings>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut ripples: Query<(
+        Entity,
+        &mut Ripple,
+        &mut Transform,
+        &Handle<StandardMaterial>,
+    )>,
+) {
+    ripples.for_each_mut(|(entity, mut ripple, mut transform, material)| {
+        ripple.age += time.delta_seconds();
+        let progress = ripple.age / settings.ripple_lifetime;
+        if progress >= 1.0 {
+            commands.entity(entity).despawn_recursive();
+            return;
+        }
+
+        transform.scale = Vec3::splat(settings.ripple_radius * progress);
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color.set_a(0.6 * (1.0 - progress));
+        }
+    });
+}
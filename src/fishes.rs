@@ -3,68 +3,68 @@ use bevy_asset_loader::prelude::*;
 use bevy_rapier3d::prelude::RigidBody::KinematicPositionBased;
 
 use crate::{
-    camera::{Tracked, TrackedZoomOnly},
-    forces::{Alignment, Cohesive, Forceable, Friction, Moveable, Separation, Wander},
-    groups::{Groupable, GroupableBundle, JoinedPlayerEvent},
+    camera::CameraTarget,
+    forces::{
+        Alignment, Cohesive, ForceWeights, Forceable, Friction, Moveable, Separation, Wander,
+    },
+    groups::{Groupable, GroupableBundle},
     input::ClickToMove,
+    states::GameState,
 };
 
+pub struct FishPlugin;
+
+impl Plugin for FishPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Fish>()
+            .add_system(fish_model_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::Running).with_system(fish_track_system),
+            );
+    }
+}
+
 #[derive(AssetCollection, Resource)]
 pub struct FishAssets {
     #[asset(path = "models/fish.glb#Scene0")]
-    fish_scene: Handle<Scene>,
+    pub fish_scene: Handle<Scene>,
     #[asset(path = "models/fish.glb#Animation0")]
-    fish_animation: Handle<AnimationClip>,
-    #[asset(path = "sounds/background.ogg")]
-    background_music: Handle<AudioSource>,
-    #[asset(path = "sounds/bubbles.ogg")]
-    bubbles_sfx: Handle<AudioSource>,
+    pub fish_animation: Handle<AnimationClip>,
 }
 
-#[derive(Component, Default)]
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 pub struct Fish;
 
-pub fn fish_animator_system(
-    fish_assets: Res<FishAssets>,
-    fishes: Query<(Entity, &Moveable, With<Fish>)>,
-    children: Query<&Children>,
-    mut players: Query<&mut AnimationPlayer>,
+pub fn fish_track_system(
+    mut commands: Commands,
+    untracked_fishes: Query<(Entity, &Groupable), (With<Fish>, Without<CameraTarget>)>,
 ) {
-    fishes.for_each(|(entity, moveable, _)| {
-        for child in children.iter_descendants(entity) {
-            if let Ok(mut player) = players.get_mut(child) {
-                if player.is_added() {
-                    player.play(fish_assets.fish_animation.clone()).repeat();
-                }
-
-                let speed = moveable.velocity.length();
-                let animation_speed = 1.0 + speed;
-                player.set_speed(animation_speed);
-            }
+    untracked_fishes.for_each(|(entity, groupable)| {
+        if groupable.is_grouped_with_player() {
+            commands.entity(entity).insert(CameraTarget::follower());
         }
     });
 }
 
-pub fn fish_joined_player_cue_system(
-    ev_joined: EventReader<JoinedPlayerEvent>,
-    fish_assets: Res<FishAssets>,
-    audio: Res<Audio>,
-) {
-    if !ev_joined.is_empty() {
-        ev_joined.clear();
-        audio.play(fish_assets.bubbles_sfx.clone());
-    }
-}
-
-pub fn fish_track_system(
+/// Gives fish spawned from a scene file the model that [`Fish::new_npc`] would have.
+pub fn fish_model_system(
     mut commands: Commands,
-    untracked_fishes: Query<(Entity, &Groupable), (With<Fish>, Without<Tracked>)>,
+    fish_assets: Option<Res<FishAssets>>,
+    added: Query<(Entity, Option<&Visibility>), (Added<Fish>, Without<Handle<Scene>>)>,
 ) {
-    untracked_fishes.for_each(|(entity, groupable)| {
-        if groupable.is_grouped_with_player() {
-            commands.entity(entity).insert(TrackedZoomOnly::default());
+    let fish_assets = match fish_assets {
+        Some(fish_assets) => fish_assets,
+        None => return,
+    };
+
+    for (entity, visibility) in added.iter() {
+        let mut entity = commands.entity(entity);
+        entity.insert(fish_assets.fish_scene.clone());
+        if visibility.is_none() {
+            entity.insert(VisibilityBundle::default());
         }
-    });
+    }
 }
 
 impl Fish {
@@ -75,9 +75,43 @@ impl Fish {
                 transform: transform,
                 ..default()
             },
+            Self::npc_behaviour(),
+        )
+    }
+
+    pub fn new_player(transform: Transform, fish_assets: &Res<FishAssets>) -> impl Bundle {
+        (
+            SceneBundle {
+                scene: fish_assets.fish_scene.clone(),
+                transform: transform,
+                ..default()
+            },
+            Self::player_behaviour(),
+        )
+    }
+
+    /// An NPC fish without a model, for simulations that run without assets.
+    pub fn new_headless_npc(transform: Transform) -> impl Bundle {
+        (
+            TransformBundle::from_transform(transform),
+            Self::npc_behaviour(),
+        )
+    }
+
+    /// A player fish without a model, for simulations that run without assets.
+    pub fn new_headless_player(transform: Transform) -> impl Bundle {
+        (
+            TransformBundle::from_transform(transform),
+            Self::player_behaviour(),
+        )
+    }
+
+    fn npc_behaviour() -> impl Bundle {
+        (
             Fish,
             Moveable::default(),
             Forceable::default(),
+            ForceWeights::default(),
             Friction::default(),
             Separation::default(),
             Cohesive::default(),
@@ -87,33 +121,17 @@ impl Fish {
         )
     }
 
-    pub fn new_player(transform: Transform, fish_assets: &Res<FishAssets>) -> impl Bundle {
+    fn player_behaviour() -> impl Bundle {
         (
-            SceneBundle {
-                scene: fish_assets.fish_scene.clone(),
-                transform: transform,
-                ..default()
-            },
             Fish,
             Moveable::default(),
+            ForceWeights::default(),
             Separation::default(),
             Cohesive::default(),
             Alignment::default(),
             ClickToMove::default(),
-            Tracked::default(),
+            CameraTarget::leader(),
             GroupableBundle::new(Groupable::player_groupable(), KinematicPositionBased),
         )
     }
 }
-
-impl FishAssets {
-    pub fn start_background_music(&self, audio: Res<Audio>) {
-        audio.play_with_settings(
-            self.background_music.clone(),
-            PlaybackSettings {
-                repeat: true,
-                ..default()
-            },
-        );
-    }
-}
@@ -2,9 +2,15 @@ use std::collections::HashMap;
 
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::RapierContext;
-use itertools::Itertools;
+use rand::Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::{groups::Groupable, random::random_direction};
+use crate::{
+    groups::Groupable,
+    random::{random_direction, PondRng},
+    states::GameState,
+};
 
 const MAX_SPEED: f32 = 20.0;
 const FRICTION_COEFFICIENT: f32 = 0.01;
@@ -14,51 +20,108 @@ const SEPARATION_RADIUS: f32 = 2.0;
 const ALIGNMENT_STRENGTH: f32 = 0.01;
 const WANDER_STRENGTH: f32 = 5.0;
 const WANDER_CHANCE: f32 = 0.1;
+const PARALLEL_BATCH_SIZE: usize = 256;
+const REDUCTION_CHUNK_SIZE: usize = 1024;
 
-#[derive(Component, Default)]
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 pub struct Moveable {
     pub velocity: Vec3,
 }
 
-#[derive(Component, Default)]
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 pub struct Forceable;
 
-#[derive(Component, Default)]
+/// Per-fish multipliers applied to each steering force before it changes velocity.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct ForceWeights {
+    pub cohesion: f32,
+    pub separation: f32,
+    pub alignment: f32,
+    pub friction: f32,
+    pub wander: f32,
+}
+
+impl Default for ForceWeights {
+    fn default() -> Self {
+        Self {
+            cohesion: 1.0,
+            separation: 1.0,
+            alignment: 1.0,
+            friction: 1.0,
+            wander: 1.0,
+        }
+    }
+}
+
+/// Fastest a fish can swim under its steering forces.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct MaxSpeed(pub f32);
+
+impl Default for MaxSpeed {
+    fn default() -> Self {
+        Self(MAX_SPEED)
+    }
+}
+
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 pub struct Cohesive {
     force: Vec3,
 }
 
-#[derive(Component, Default)]
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 pub struct Separation {
     force: Vec3,
 }
 
-#[derive(Component, Default)]
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 pub struct Alignment {
     force: Vec3,
 }
 
-#[derive(Component, Default)]
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 pub struct Friction {
     force: Vec3,
 }
 
-#[derive(Component, Default)]
+/// Unlike the other forces, a wander force lasts until it is randomly replaced,
+/// so it is part of the pond's state.
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 pub struct Wander {
-    force: Vec3,
+    pub force: Vec3,
 }
 
 pub struct ForcesPlugin;
 
 impl Plugin for ForcesPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(move_system)
-            .add_system(apply_forces_system)
-            .add_system(cohesion_force_system)
-            .add_system(separation_force_system)
-            .add_system(alignment_force_system)
-            .add_system(friction_force_system)
-            .add_system(wander_force_system);
+        app.register_type::<Moveable>()
+            .register_type::<Forceable>()
+            .register_type::<ForceWeights>()
+            .register_type::<MaxSpeed>()
+            .register_type::<Cohesive>()
+            .register_type::<Separation>()
+            .register_type::<Alignment>()
+            .register_type::<Friction>()
+            .register_type::<Wander>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Running)
+                    .with_system(move_system)
+                    .with_system(apply_forces_system)
+                    .with_system(cohesion_force_system)
+                    .with_system(separation_force_system)
+                    .with_system(alignment_force_system)
+                    .with_system(friction_force_system)
+                    .with_system(wander_force_system),
+            );
     }
 }
 
@@ -84,60 +147,52 @@ pub fn apply_forces_system(
             Option<&Alignment>,
             Option<&Friction>,
             Option<&Wander>,
+            Option<&ForceWeights>,
+            Option<&MaxSpeed>,
         ),
         With<Forceable>,
     >,
 ) {
     let delta_time = time.delta_seconds();
     bodies.for_each_mut(
-        |(mut moveable, cohesive, separation, alignment, friction, wander)| {
+        |(mut moveable, cohesive, separation, alignment, friction, wander, weights, max_speed)| {
+            let weights = weights.copied().unwrap_or_default();
+
             if let Some(c) = cohesive {
-                moveable.velocity += delta_time * c.force;
+                moveable.velocity += delta_time * weights.cohesion * c.force;
             }
 
             if let Some(s) = separation {
-                moveable.velocity += delta_time * s.force;
+                moveable.velocity += delta_time * weights.separation * s.force;
             }
 
             if let Some(a) = alignment {
-                moveable.velocity += delta_time * a.force;
+                moveable.velocity += delta_time * weights.alignment * a.force;
             }
 
             if let Some(f) = friction {
-                moveable.velocity += delta_time * f.force;
+                moveable.velocity += delta_time * weights.friction * f.force;
             }
 
             if let Some(w) = wander {
-                moveable.velocity += delta_time * w.force;
+                moveable.velocity += delta_time * weights.wander * w.force;
             }
 
-            moveable.velocity = moveable.velocity.clamp_length_max(MAX_SPEED);
+            let max_speed = max_speed.copied().unwrap_or_default();
+            moveable.velocity = moveable.velocity.clamp_length_max(max_speed.0);
         },
     );
 }
 
 pub fn cohesion_force_system(mut cohesives: Query<(&Transform, &mut Cohesive, &Groupable)>) {
-    let group_com_map: HashMap<usize, Vec3> = cohesives
+    let positions: Vec<(usize, Vec3)> = cohesives
         .iter()
-        .filter_map(|(t, c, g)| match g.id {
-            Some(id) => Some((t, c, id)),
-            None => None,
-        })
-        .group_by(|(_, _, id)| *id)
-        .into_iter()
-        .map(|(id, group)| {
-            let (position_summed, count) = group
-                .fold((Vec3::ZERO, 0), |(com, count), (t, _, _)| {
-                    (com + t.translation, count + 1)
-                });
-
-            let group_center_of_mass = (1.0 / count as f32) * position_summed;
-
-            return (id, group_center_of_mass);
-        })
+        .filter_map(|(t, _, g)| g.id.map(|id| (id, t.translation)))
         .collect();
 
-    cohesives.for_each_mut(|(t, mut c, g)| {
+    let group_com_map = group_averages(&positions);
+
+    cohesives.par_for_each_mut(PARALLEL_BATCH_SIZE, |(t, mut c, g)| {
         if let Some(id) = g.id {
             if let Some(group_center_of_mass) = group_com_map.get(&id) {
                 c.force = COHESION_STRENGTH * (*group_center_of_mass - t.translation);
@@ -148,65 +203,105 @@ pub fn cohesion_force_system(mut cohesives: Query<(&Transform, &mut Cohesive, &G
 
 pub fn separation_force_system(
     rapier: Res<RapierContext>,
-    mut separations: Query<(&Transform, &mut Separation)>,
+    mut separations: Query<(Entity, &Transform, &mut Separation)>,
 ) {
-    separations.for_each_mut(|(_, mut separation)| separation.force = Vec3::ZERO);
+    let positions: HashMap<Entity, Vec3> = separations
+        .iter()
+        .map(|(e, t, _)| (e, t.translation))
+        .collect();
 
+    // Neighbours are kept in intersection order so each force sums the same way
+    // no matter how entities are split across threads.
+    let mut neighbours: HashMap<Entity, Vec<Entity>> = HashMap::new();
     for (e1, e2, _) in rapier.intersection_pairs() {
-        if let Ok([(t1, mut s1), (t2, mut s2)]) = separations.get_many_mut([e1, e2]) {
-            let delta = t1.translation - t2.translation;
-            let distance = delta.length().abs();
-
-            if distance > f32::EPSILON {
-                let r = distance / SEPARATION_RADIUS;
-                let r3 = r * r * r;
-                let separation_impulse = (SEPARATION_STRENGTH / r3) * delta;
-                s1.force += separation_impulse;
-                s2.force -= separation_impulse;
-            }
+        if e1 != e2 && positions.contains_key(&e1) && positions.contains_key(&e2) {
+            neighbours.entry(e1).or_default().push(e2);
+            neighbours.entry(e2).or_default().push(e1);
         }
     }
+
+    separations.par_for_each_mut(PARALLEL_BATCH_SIZE, |(e, t, mut separation)| {
+        separation.force = neighbours.get(&e).map_or(Vec3::ZERO, |others| {
+            others.iter().fold(Vec3::ZERO, |force, other| {
+                force + separation_impulse(t.translation - positions[other])
+            })
+        });
+    });
+}
+
+fn separation_impulse(delta: Vec3) -> Vec3 {
+    let distance = delta.length().abs();
+
+    if distance > f32::EPSILON {
+        let r = distance / SEPARATION_RADIUS;
+        let r3 = r * r * r;
+        (SEPARATION_STRENGTH / r3) * delta
+    } else {
+        Vec3::ZERO
+    }
 }
 
-pub fn wander_force_system(mut wanders: Query<(&mut Wander, &Groupable)>) {
+pub fn wander_force_system(
+    mut rng: ResMut<PondRng>,
+    mut wanders: Query<(&mut Wander, &Groupable)>,
+) {
+    let rng = rng.rng();
     wanders.for_each_mut(|(mut w, g)| {
-        if g.id == None && rand::random::<f32>() < WANDER_CHANCE {
-            let strength = WANDER_STRENGTH * rand::random::<f32>();
-            w.force = strength * random_direction();
+        if g.id == None && rng.gen::<f32>() < WANDER_CHANCE {
+            let strength = WANDER_STRENGTH * rng.gen::<f32>();
+            w.force = strength * random_direction(rng);
         }
     });
 }
 
 pub fn alignment_force_system(mut cohesives: Query<(&Moveable, &mut Alignment, &Groupable)>) {
-    let group_alignment_force_map: HashMap<usize, Vec3> = cohesives
+    let velocities: Vec<(usize, Vec3)> = cohesives
         .iter()
-        .filter_map(|(m, a, g)| match g.id {
-            Some(id) => Some((m, a, id)),
-            None => None,
-        })
-        .group_by(|(_, _, id)| *id)
-        .into_iter()
-        .map(|(id, group)| {
-            let (velocity_summed, count) = group
-                .fold((Vec3::ZERO, 0), |(velocity, count), (m, _, _)| {
-                    (velocity + m.velocity, count + 1)
-                });
-
-            let average_velocity = (1.0 / count as f32) * velocity_summed;
-
-            return (id, ALIGNMENT_STRENGTH * average_velocity);
-        })
+        .filter_map(|(m, _, g)| g.id.map(|id| (id, m.velocity)))
         .collect();
 
-    cohesives.for_each_mut(|(_, mut a, g)| {
+    let group_average_velocity_map = group_averages(&velocities);
+
+    cohesives.par_for_each_mut(PARALLEL_BATCH_SIZE, |(_, mut a, g)| {
         if let Some(id) = g.id {
-            if let Some(alignment_force) = group_alignment_force_map.get(&id) {
-                a.force = *alignment_force;
+            if let Some(average_velocity) = group_average_velocity_map.get(&id) {
+                a.force = ALIGNMENT_STRENGTH * *average_velocity;
             }
         }
     });
 }
 
+/// Averages `values` per group id.
+///
+/// Partial sums are taken over fixed-size chunks in parallel and then combined in
+/// chunk order, so the result is identical regardless of the number of threads.
+fn group_averages(values: &[(usize, Vec3)]) -> HashMap<usize, Vec3> {
+    values
+        .par_chunks(REDUCTION_CHUNK_SIZE)
+        .map(|chunk| {
+            let mut sums: HashMap<usize, (Vec3, usize)> = HashMap::new();
+            for (id, value) in chunk {
+                let (sum, count) = sums.entry(*id).or_insert((Vec3::ZERO, 0));
+                *sum += *value;
+                *count += 1;
+            }
+            sums
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .fold(HashMap::new(), |mut totals, sums| {
+            for (id, (sum, count)) in sums {
+                let (total_sum, total_count) = totals.entry(id).or_insert((Vec3::ZERO, 0));
+                *total_sum += sum;
+                *total_count += count;
+            }
+            totals
+        })
+        .into_iter()
+        .map(|(id, (sum, count))| (id, (1.0 / count as f32) * sum))
+        .collect()
+}
+
 pub fn friction_force_system(mut moveables: Query<(&Moveable, &mut Friction)>) {
     moveables.for_each_mut(|(fish, mut friction)| {
         friction.force = -1.0 * FRICTION_COEFFICIENT * fish.velocity;
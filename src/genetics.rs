@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    fishes::Fish,
+    forces::{ForceWeights, MaxSpeed},
+    lifecycle::FishBornEvent,
+    random::PondRng,
+    states::GameState,
+};
+
+pub struct GeneticsPlugin;
+
+impl Plugin for GeneticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MutationSettings>()
+            .register_type::<Genome>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Running)
+                    .with_system(assign_genome_system)
+                    .with_system(inherit_genome_system.after(assign_genome_system))
+                    .with_system(express_genome_system.after(inherit_genome_system)),
+            );
+    }
+}
+
+/// Heritable steering preferences, expressed as a fish's [`ForceWeights`] and [`MaxSpeed`].
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct Genome {
+    pub cohesion: f32,
+    pub separation: f32,
+    pub alignment: f32,
+    pub wander: f32,
+    pub max_speed: f32,
+}
+
+impl Default for Genome {
+    fn default() -> Self {
+        let weights = ForceWeights::default();
+        Self {
+            cohesion: weights.cohesion,
+            separation: weights.separation,
+            alignment: weights.alignment,
+            wander: weights.wander,
+            max_speed: MaxSpeed::default().0,
+        }
+    }
+}
+
+/// How genomes change from one generation to the next.
+#[derive(Resource, Debug, Clone)]
+pub struct MutationSettings {
+    /// Chance of each gene mutating when a fish is born.
+    pub rate: f32,
+    /// Largest relative change a single mutation makes to a gene.
+    pub strength: f32,
+    /// Range every force weight gene is kept within.
+    pub weight_range: (f32, f32),
+    pub max_speed_range: (f32, f32),
+}
+
+impl Default for MutationSettings {
+    fn default() -> Self {
+        Self {
+            rate: 0.2,
+            strength: 0.25,
+            weight_range: (0.0, 3.0),
+            max_speed_range: (10.0, 30.0),
+        }
+    }
+}
+
+impl Genome {
+    /// A child's genome: each gene comes from either parent, then mutates.
+    pub fn inherit(
+        a: &Genome,
+        b: &Genome,
+        rng: &mut impl Rng,
+        settings: &MutationSettings,
+    ) -> Self {
+        let mut pick = |x: f32, y: f32| if rng.gen::<bool>() { x } else { y };
+        let child = Self {
+            cohesion: pick(a.cohesion, b.cohesion),
+            separation: pick(a.separation, b.separation),
+            alignment: pick(a.alignment, b.alignment),
+            wander: pick(a.wander, b.wander),
+            max_speed: pick(a.max_speed, b.max_speed),
+        };
+
+        child.mutate(rng, settings)
+    }
+
+    /// A copy of this genome where each gene has a `settings.rate` chance of being scaled
+    /// by up to `settings.strength` either way. The same RNG state always gives the same result.
+    pub fn mutate(&self, rng: &mut impl Rng, settings: &MutationSettings) -> Self {
+        let mut gene = |value: f32, (min, max): (f32, f32)| {
+            let value = if rng.gen::<f32>() < settings.rate {
+                let change: f32 = rng.gen_range(-1.0..=1.0);
+                value * (1.0 + settings.strength * change)
+            } else {
+                value
+            };
+            value.clamp(min, max)
+        };
+
+        Self {
+            cohesion: gene(self.cohesion, settings.weight_range),
+            separation: gene(self.separation, settings.weight_range),
+            alignment: gene(self.alignment, settings.weight_range),
+            wander: gene(self.wander, settings.weight_range),
+            max_speed: gene(self.max_speed, settings.max_speed_range),
+        }
+    }
+
+    /// Force weights for this genome; friction isn't inherited and keeps `base`'s weight.
+    pub fn weights(&self, base: ForceWeights) -> ForceWeights {
+        ForceWeights {
+            cohesion: self.cohesion,
+            separation: self.separation,
+            alignment: self.alignment,
+            wander: self.wander,
+            ..base
+        }
+    }
+}
+
+/// Fish spawned without a genome get the default one.
+fn assign_genome_system(
+    mut commands: Commands,
+    fishes: Query<Entity, (With<Fish>, Without<Genome>)>,
+) {
+    fishes.for_each(|entity| {
+        commands.entity(entity).insert(Genome::default());
+    });
+}
+
+fn inherit_genome_system(
+    mut commands: Commands,
+    settings: Res<MutationSettings>,
+    mut pond_rng: ResMut<PondRng>,
+    mut ev_born: EventReader<FishBornEvent>,
+    genomes: Query<&Genome>,
+) {
+    for born in ev_born.iter() {
+        let [a, b] = born
+            .parents
+            .map(|parent| genomes.get(parent).copied().unwrap_or_default());
+        let genome = Genome::inherit(&a, &b, pond_rng.rng(), &settings);
+        commands.entity(born.child).insert(genome);
+    }
+}
+
+fn express_genome_system(
+    mut commands: Commands,
+    fishes: Query<(Entity, &Genome, Option<&ForceWeights>), Changed<Genome>>,
+) {
+    fishes.for_each(|(entity, genome, weights)| {
+        commands.entity(entity).insert((
+            genome.weights(weights.copied().unwrap_or_default()),
+            MaxSpeed(genome.max_speed),
+        ));
+    });
+}
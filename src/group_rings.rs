@@ -0,0 +1,186 @@
+use std::{collections::HashMap, f32::consts::FRAC_PI_2};
+
+use bevy::prelude::*;
+
+use crate::{
+    fishes::Fish,
+    groups::Groupable,
+    minimap::group_color,
+    states::{GameState, Session},
+};
+
+pub struct GroupRingsPlugin;
+
+impl Plugin for GroupRingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GroupRingSettings>()
+            .init_resource::<GroupRingMaterials>()
+            .add_startup_system(setup_group_rings)
+            .add_system(toggle_group_rings_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::Running)
+                    .with_system(spawn_group_rings_system.after(toggle_group_rings_system))
+                    .with_system(group_ring_system.after(spawn_group_rings_system)),
+            );
+    }
+}
+
+/// A debugging view that draws a ring under every fish, coloured by its group id.
+#[derive(Resource, Debug, Clone)]
+pub struct GroupRingSettings {
+    pub enabled: bool,
+    /// Key that turns the rings on and off.
+    pub toggle_key: KeyCode,
+    pub radius: f32,
+    /// Rings of the player's school are white and this much larger.
+    pub player_scale: f32,
+    /// Distance below the fish the rings are drawn at.
+    pub depth: f32,
+}
+
+impl Default for GroupRingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            toggle_key: KeyCode::G,
+            radius: 3.0,
+            player_scale: 1.5,
+            depth: 1.0,
+        }
+    }
+}
+
+/// One unlit material per group id, shared by every ring in that group.
+#[derive(Resource, Default)]
+struct GroupRingMaterials {
+    ring_mesh: Handle<Mesh>,
+    by_group: HashMap<Option<usize>, Handle<StandardMaterial>>,
+}
+
+impl GroupRingMaterials {
+    fn get_or_add(
+        &mut self,
+        group_id: Option<usize>,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Handle<StandardMaterial> {
+        self.by_group
+            .entry(group_id)
+            .or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    base_color: group_color(group_id),
+                    unlit: true,
+                    ..default()
+                })
+            })
+            .clone()
+    }
+}
+
+/// A ring drawn under `fish`. Rings aren't children of their fish so the fish's
+/// rotation, scale and scene materials leave them alone.
+#[derive(Component)]
+pub struct GroupRing {
+    pub fish: Entity,
+}
+
+/// Marks a fish that already has a [`GroupRing`].
+#[derive(Component)]
+pub struct HasGroupRing;
+
+fn setup_group_rings(
+    mut ring_materials: ResMut<GroupRingMaterials>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    ring_materials.ring_mesh = meshes.add(Mesh::from(shape::Torus {
+        radius: 1.0,
+        ring_radius: 0.1,
+        subdivisions_segments: 24,
+        subdivisions_sides: 4,
+    }));
+}
+
+fn toggle_group_rings_system(keys: Res<Input<KeyCode>>, mut settings: ResMut<GroupRingSettings>) {
+    if keys.just_pressed(settings.toggle_key) {
+        settings.enabled = !settings.enabled;
+    }
+}
+
+fn spawn_group_rings_system(
+    mut commands: Commands,
+    settings: Res<GroupRingSettings>,
+    mut ring_materials: ResMut<GroupRingMaterials>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    fishes: Query<(Entity, &Groupable), (With<Fish>, Without<HasGroupRing>)>,
+) {
+    if !settings.enabled {
+        return;
+    }
+
+    fishes.for_each(|(entity, groupable)| {
+        let material = ring_materials.get_or_add(groupable.id, &mut materials);
+        commands.spawn((
+            PbrBundle {
+                mesh: ring_materials.ring_mesh.clone(),
+                material,
+                // The torus lies flat in the XZ plane; turn it to lie in the pond's plane.
+                transform: Transform::from_rotation(Quat::from_rotation_x(FRAC_PI_2)),
+                // Shown once it has been moved under its fish.
+                visibility: Visibility::INVISIBLE,
+                ..default()
+            },
+            GroupRing { fish: entity },
+            Session,
+        ));
+        commands.entity(entity).insert(HasGroupRing);
+    });
+}
+
+/// Keeps each ring under its fish and in its fish's group colour, and hides
+/// every ring while the view is off.
+fn group_ring_system(
+    mut commands: Commands,
+    settings: Res<GroupRingSettings>,
+    mut ring_materials: ResMut<GroupRingMaterials>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    fishes: Query<(&Transform, &Groupable), Without<GroupRing>>,
+    mut rings: Query<(
+        Entity,
+        &GroupRing,
+        &mut Transform,
+        &mut Visibility,
+        &mut Handle<StandardMaterial>,
+    )>,
+) {
+    rings.for_each_mut(
+        |(entity, ring, mut transform, mut visibility, mut material)| {
+            let (fish_transform, groupable) = match fishes.get(ring.fish) {
+                Ok(fish) => fish,
+                Err(_) => {
+                    commands.entity(entity).despawn_recursive();
+                    return;
+                }
+            };
+
+            if visibility.is_visible != settings.enabled {
+                visibility.is_visible = settings.enabled;
+            }
+            if !settings.enabled {
+                return;
+            }
+
+            let scale = if groupable.is_grouped_with_player() {
+                settings.player_scale
+            } else {
+                1.0
+            };
+            transform.translation =
+                fish_transform.translation - Vec3::new(0.0, 0.0, settings.depth);
+            transform.scale = Vec3::splat(scale * settings.radius);
+
+            let group_material = ring_materials.get_or_add(groupable.id, &mut materials);
+            if *material != group_material {
+                *material = group_material;
+            }
+        },
+    );
+}
@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
+use crate::states::GameState;
+
 use std::{
     collections::HashMap,
     sync::atomic::{AtomicUsize, Ordering},
@@ -14,10 +16,14 @@ pub struct GroupsPlugin;
 
 impl Plugin for GroupsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(group_by_proximity_system)
-            .add_system(merge_groups_system)
-            .add_event::<MergeGroupsEvent>()
-            .add_event::<JoinedPlayerEvent>();
+        app.register_type::<Groupable>()
+            .add_system(groupable_physics_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::Running)
+                    .with_system(group_by_proximity_system)
+                    .with_system(merge_groups_system),
+            )
+            .add_event::<MergeGroupsEvent>();
     }
 }
 
@@ -44,7 +50,8 @@ impl GroupableBundle {
     }
 }
 
-#[derive(Component, Default)]
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 pub struct Groupable {
     pub id: Option<usize>,
 }
@@ -63,11 +70,43 @@ impl Groupable {
     }
 }
 
-struct MergeGroupsEvent(usize, usize);
+/// Gives groupables spawned from a scene file the parts of a [`GroupableBundle`] that
+/// can't be reflected.
+pub fn groupable_physics_system(
+    mut commands: Commands,
+    added: Query<(Entity, Option<&RigidBody>), (Added<Groupable>, Without<Collider>)>,
+) {
+    for (entity, rigidbody) in added.iter() {
+        let mut entity = commands.entity(entity);
+        entity.insert((
+            Collider::ball(GROUP_DISTANCE / 2.0),
+            Sensor,
+            ActiveCollisionTypes::all(),
+            ActiveEvents::COLLISION_EVENTS,
+        ));
+        if rigidbody.is_none() {
+            entity.insert(RigidBody::KinematicPositionBased);
+        }
+    }
+}
 
-pub struct JoinedPlayerEvent;
+/// The id the next newly formed group will receive.
+pub fn next_group_id() -> usize {
+    AVAILABLE_GROUP_ID.load(Ordering::Relaxed)
+}
 
-fn group_by_proximity_system(
+/// Resets the group id registry, e.g. when restoring a saved pond.
+pub fn set_next_group_id(id: usize) {
+    AVAILABLE_GROUP_ID.store(id, Ordering::Relaxed);
+}
+
+pub struct MergeGroupsEvent(usize, usize);
+
+/// Sent when a fish joins the player's school; holds the fish that joined. A group
+/// merging into the school sends one for each of its fish.
+pub struct JoinedPlayerEvent(pub Entity);
+
+pub fn group_by_proximity_system(
     mut ev_collision: EventReader<CollisionEvent>,
     mut groupables: Query<&mut Groupable>,
     mut ev_merge: EventWriter<MergeGroupsEvent>,
@@ -84,21 +123,18 @@ fn group_by_proximity_system(
                     }
                     (Some(id), None) => {
                         if id == 0 {
-                            ev_join.send(JoinedPlayerEvent);
+                            ev_join.send(JoinedPlayerEvent(*e2));
                         }
                         g2.id = Some(id)
                     }
                     (None, Some(id)) => {
                         if id == 0 {
-                            ev_join.send(JoinedPlayerEvent);
+                            ev_join.send(JoinedPlayerEvent(*e1));
                         }
                         g1.id = Some(id)
                     }
                     (Some(id1), Some(id2)) => {
                         if id1 != id2 {
-                            if id1 == 0 || id2 == 0 {
-                                ev_join.send(JoinedPlayerEvent);
-                            }
                             ev_merge.send(MergeGroupsEvent(id1, id2));
                         }
                     }
@@ -109,17 +145,21 @@ fn group_by_proximity_system(
 }
 
 fn merge_groups_system(
-    mut groupables: Query<&mut Groupable>,
+    mut groupables: Query<(Entity, &mut Groupable)>,
     mut ev_merge: EventReader<MergeGroupsEvent>,
+    mut ev_join: EventWriter<JoinedPlayerEvent>,
 ) {
     let replacement_map: HashMap<usize, usize> = ev_merge
         .iter()
         .map(|MergeGroupsEvent(id1, id2)| (usize::max(*id1, *id2), usize::min(*id1, *id2)))
         .collect();
 
-    groupables.for_each_mut(|mut groupable| {
+    groupables.for_each_mut(|(entity, mut groupable)| {
         if let Some(id) = groupable.id {
             if let Some(new_id) = replacement_map.get(&id) {
+                if *new_id == 0 {
+                    ev_join.send(JoinedPlayerEvent(entity));
+                }
                 groupable.id = Some(*new_id);
             }
         }
@@ -0,0 +1,73 @@
+use bevy::{
+    hierarchy::HierarchyPlugin, prelude::*, time::TimeUpdateStrategy, transform::TransformPlugin,
+    utils::Duration,
+};
+use bevy_rapier3d::prelude::{NoUserData, RapierPhysicsPlugin};
+
+use crate::{
+    forces::ForcesPlugin,
+    groups::GroupsPlugin,
+    random::PondRng,
+    states::{GameState, StatesPlugin},
+};
+
+/// Runs the flocking and grouping simulation without rendering, audio or assets.
+///
+/// Meant to be added after `MinimalPlugins`; spawn fish with
+/// [`Fish::new_headless_npc`](crate::fishes::Fish::new_headless_npc) and
+/// [`Fish::new_headless_player`](crate::fishes::Fish::new_headless_player).
+pub struct HeadlessSimPlugin {
+    /// Seconds advanced per update. `None` follows the wall clock instead.
+    pub timestep: Option<f32>,
+    /// Seed for [`PondRng`]. `None` picks one at random.
+    pub seed: Option<u64>,
+}
+
+impl Default for HeadlessSimPlugin {
+    fn default() -> Self {
+        Self {
+            timestep: Some(1.0 / 60.0),
+            seed: None,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct HeadlessTimestep(Duration);
+
+impl Plugin for HeadlessSimPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(TransformPlugin)
+            .add_plugin(HierarchyPlugin)
+            .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+            .add_state(GameState::Running);
+
+        if let Some(seed) = self.seed {
+            app.insert_resource(PondRng::new(seed));
+        }
+
+        app.add_plugin(StatesPlugin)
+            .add_plugin(ForcesPlugin)
+            .add_plugin(GroupsPlugin);
+
+        if let Some(timestep) = self.timestep {
+            let startup = app
+                .world
+                .get_resource_or_insert_with(Time::default)
+                .startup();
+            app.insert_resource(HeadlessTimestep(Duration::from_secs_f32(timestep)))
+                .insert_resource(TimeUpdateStrategy::ManualInstant(startup))
+                .add_system_to_stage(CoreStage::Last, headless_timestep_system);
+        }
+    }
+}
+
+/// Schedules the next [`Time`] update exactly one timestep after the last one.
+fn headless_timestep_system(
+    time: Res<Time>,
+    timestep: Res<HeadlessTimestep>,
+    mut update_strategy: ResMut<TimeUpdateStrategy>,
+) {
+    let last_update = time.last_update().unwrap_or_else(|| time.startup());
+    *update_strategy = TimeUpdateStrategy::ManualInstant(last_update + timestep.0);
+}
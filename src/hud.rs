@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+
+use crate::{
+    groups::{Groupable, JoinedPlayerEvent},
+    states::{GameState, Session},
+};
+
+const HUD_FONT_SIZE: f32 = 24.0;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HudStats>()
+            .add_system_set(SystemSet::on_enter(GameState::Running).with_system(setup_hud))
+            .add_system_set(
+                SystemSet::on_update(GameState::Running)
+                    .with_system(hud_stats_system)
+                    .with_system(hud_elapsed_time_system),
+            )
+            .add_system(hud_text_system);
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct UiAssets {
+    #[asset(path = "fonts/DejaVuSans.ttf")]
+    pub font: Handle<Font>,
+}
+
+#[derive(Resource, Default)]
+pub struct HudStats {
+    pub school_size: usize,
+    pub fish_collected: usize,
+    pub elapsed_seconds: f32,
+    pub largest_rival_group: usize,
+}
+
+#[derive(Component)]
+pub struct HudText;
+
+pub fn setup_hud(mut commands: Commands, ui_assets: Res<UiAssets>, mut stats: ResMut<HudStats>) {
+    // Stats are kept per session.
+    *stats = HudStats::default();
+
+    commands.spawn((
+        Session,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: ui_assets.font.clone(),
+                font_size: HUD_FONT_SIZE,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(10.0),
+                top: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+        HudText,
+    ));
+}
+
+fn hud_stats_system(
+    mut stats: ResMut<HudStats>,
+    mut ev_joined: EventReader<JoinedPlayerEvent>,
+    changed: Query<(), Changed<Groupable>>,
+    groupables: Query<&Groupable>,
+) {
+    // Counted from joins rather than school growth, so fish born into the school
+    // aren't collected.
+    let joined = ev_joined.iter().count();
+    if joined > 0 {
+        stats.fish_collected += joined;
+    }
+    if joined == 0 && changed.is_empty() {
+        return;
+    }
+
+    let mut group_sizes: HashMap<usize, usize> = HashMap::new();
+    groupables.for_each(|groupable| {
+        if let Some(id) = groupable.id {
+            *group_sizes.entry(id).or_default() += 1;
+        }
+    });
+
+    let school_size = group_sizes.get(&0).copied().unwrap_or_default();
+    let largest_rival_group = group_sizes
+        .iter()
+        .filter(|(id, _)| **id != 0)
+        .map(|(_, size)| *size)
+        .max()
+        .unwrap_or_default();
+
+    stats.school_size = school_size;
+    stats.largest_rival_group = largest_rival_group;
+}
+
+fn hud_elapsed_time_system(time: Res<Time>, mut stats: ResMut<HudStats>) {
+    stats.elapsed_seconds += time.delta_seconds();
+}
+
+fn hud_text_system(stats: Res<HudStats>, mut texts: Query<&mut Text, With<HudText>>) {
+    if !stats.is_changed() {
+        return;
+    }
+
+    let elapsed = stats.elapsed_seconds as u32;
+    texts.for_each_mut(|mut text| {
+        text.sections[0].value = format!(
+            "School: {}\nCollected: {}\nTime: {}:{:02}\nLargest rival: {}",
+            stats.school_size,
+            stats.fish_collected,
+            elapsed / 60,
+            elapsed % 60,
+            stats.largest_rival_group,
+        );
+    });
+}
@@ -1,9 +1,22 @@
 use crate::forces::Moveable;
-use bevy::prelude::*;
+use bevy::{prelude::*, render::camera::RenderTarget, window::WindowId};
+
+use crate::states::GameState;
 
 const MAX_SPEED: f32 = 30.0;
 
-#[derive(Component, Default)]
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ClickToMove>().add_system_set(
+            SystemSet::on_update(GameState::Running).with_system(click_to_move_system),
+        );
+    }
+}
+
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 pub struct ClickToMove;
 
 pub fn click_to_move_system(
@@ -33,7 +46,11 @@ pub fn try_get_cursor_position_on_z_plane(
     windows: Res<Windows>,
     cameras: Query<(&Camera, &GlobalTransform)>,
 ) -> Option<Vec3> {
-    if let Ok((camera, camera_transform)) = cameras.get_single() {
+    let primary_camera = cameras.iter().find(|(camera, _)| {
+        matches!(camera.target, RenderTarget::Window(id) if id == WindowId::primary())
+    });
+
+    if let Some((camera, camera_transform)) = primary_camera {
         if let Some(window) = windows.get_primary() {
             if let Some(viewport_position) = window.cursor_position() {
                 if let Some(ray) = camera.viewport_to_world(camera_transform, viewport_position) {
@@ -0,0 +1,421 @@
+use std::{fs, io, path::Path};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use bevy_asset_loader::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    fishes::{Fish, FishAssets},
+    forces::Moveable,
+    groups::{Groupable, JoinedPlayerEvent},
+    random::{random_direction, PondRng},
+    states::{GameState, Session},
+};
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Level>()
+            .register_type::<Obstacle>()
+            .init_resource::<SpawnOverrides>()
+            .init_asset_loader::<LevelLoader>()
+            .add_system_set(SystemSet::on_enter(GameState::Running).with_system(spawn_level))
+            .add_system_set(
+                SystemSet::on_update(GameState::Running)
+                    .with_system(pond_bounds_system)
+                    .with_system(obstacle_collision_system)
+                    .with_system(level_objectives_system),
+            );
+    }
+}
+
+/// Dynamic asset key the level file is registered under.
+pub const LEVEL_ASSET_KEY: &str = "level";
+pub const DEFAULT_LEVEL_PATH: &str = "levels/pond.level.ron";
+
+#[derive(AssetCollection, Resource)]
+pub struct LevelAssets {
+    #[asset(key = "level")]
+    pub level: Handle<Level>,
+}
+
+/// Registers the level file to load, relative to the assets directory.
+///
+/// Must be called after the loading state has been added.
+pub fn register_level_path(app: &mut App, path: &str) {
+    app.world
+        .get_resource_or_insert_with(DynamicAssets::default)
+        .register_asset(
+            LEVEL_ASSET_KEY,
+            Box::new(StandardDynamicAsset::File {
+                path: path.to_string(),
+            }),
+        );
+}
+
+/// Replaces a level's spawn areas with a single one around the origin.
+///
+/// Fields left as `None` keep the level's total fish count or the default radius.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct SpawnOverrides {
+    pub fish_count: Option<usize>,
+    pub spawn_radius: Option<f32>,
+}
+
+impl SpawnOverrides {
+    fn is_empty(&self) -> bool {
+        self.fish_count.is_none() && self.spawn_radius.is_none()
+    }
+}
+
+const DEFAULT_SPAWN_RADIUS: f32 = 1000.0;
+
+/// A playable pond, loaded from a `.level.ron` file.
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "d2757c28-93ce-49ea-9378-66b3e221fb3a"]
+pub struct Level {
+    pub name: String,
+    pub pond: PondShape,
+    pub player_spawn: [f32; 2],
+    pub spawns: Vec<SpawnArea>,
+    #[serde(default)]
+    pub obstacles: Vec<Obstacle>,
+    pub win: WinCondition,
+    #[serde(default)]
+    pub lose: Vec<LoseCondition>,
+}
+
+impl Level {
+    /// Reads a level file directly, for apps without an asset server.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        ron::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Where the level's NPC fish start, with `overrides` replacing its spawn areas.
+    pub fn npc_transforms(&self, overrides: &SpawnOverrides, rng: &mut impl Rng) -> Vec<Transform> {
+        let overridden;
+        let spawns = if overrides.is_empty() {
+            &self.spawns[..]
+        } else {
+            overridden = [SpawnArea {
+                center: [0.0, 0.0],
+                radius: overrides.spawn_radius.unwrap_or(DEFAULT_SPAWN_RADIUS),
+                count: overrides
+                    .fish_count
+                    .unwrap_or_else(|| self.spawns.iter().map(|area| area.count).sum()),
+            }];
+            &overridden[..]
+        };
+
+        let mut transforms = Vec::new();
+        for area in spawns.iter() {
+            let center = Vec2::from(area.center).extend(0.0);
+            for _ in 0..area.count {
+                let length: f32 = area.radius * rng.gen::<f32>();
+                let translation = center + length * random_direction(rng);
+                let direction = random_direction(rng);
+
+                transforms.push(
+                    Transform::from_translation(translation)
+                        .looking_at(translation + direction, Vec3::Z),
+                );
+            }
+        }
+        transforms
+    }
+
+    pub fn player_transform(&self) -> Transform {
+        let translation = Vec2::from(self.player_spawn).extend(0.0);
+        Transform::from_translation(translation).looking_at(translation + Vec3::Y, Vec3::Z)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum PondShape {
+    Circle { radius: f32 },
+    Rectangle { half_width: f32, half_height: f32 },
+}
+
+impl PondShape {
+    /// Distance from the center to the farthest edge along either axis.
+    pub fn half_extent(&self) -> f32 {
+        match *self {
+            PondShape::Circle { radius } => radius,
+            PondShape::Rectangle {
+                half_width,
+                half_height,
+            } => half_width.max(half_height),
+        }
+    }
+
+    /// Closest point to `position` that lies inside the pond.
+    pub fn clamp(&self, position: Vec2) -> Vec2 {
+        match *self {
+            PondShape::Circle { radius } => position.clamp_length_max(radius),
+            PondShape::Rectangle {
+                half_width,
+                half_height,
+            } => position.clamp(
+                Vec2::new(-half_width, -half_height),
+                Vec2::new(half_width, half_height),
+            ),
+        }
+    }
+}
+
+/// `count` NPC fish scattered uniformly at random within `radius` of `center`.
+#[derive(Deserialize, Debug)]
+pub struct SpawnArea {
+    pub center: [f32; 2],
+    pub radius: f32,
+    pub count: usize,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, Component, Reflect)]
+#[reflect(Component)]
+pub struct Obstacle {
+    pub center: [f32; 2],
+    pub radius: f32,
+}
+
+#[derive(Deserialize, Debug)]
+pub enum WinCondition {
+    /// Collect at least `count` fish into the player's school.
+    GatherFish { count: usize },
+    /// Bring the center of the player's school within `radius` of `center`.
+    ReachGoal { center: [f32; 2], radius: f32 },
+}
+
+#[derive(Deserialize, Debug)]
+pub enum LoseCondition {
+    TimeLimit {
+        seconds: f32,
+    },
+    /// A rival group grows to `size` fish.
+    RivalGroupSize {
+        size: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LevelOutcome {
+    Won,
+    Lost(String),
+}
+
+pub struct LevelCompleteEvent(pub LevelOutcome);
+
+/// The level being played and how far along it is.
+#[derive(Resource)]
+pub struct CurrentLevel {
+    pub handle: Handle<Level>,
+    pub elapsed_seconds: f32,
+    /// Fish that have joined the player's school during the level.
+    pub fish_collected: usize,
+    pub outcome: Option<LevelOutcome>,
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let level = ron::de::from_bytes::<Level>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+fn spawn_level(
+    mut commands: Commands,
+    level_assets: Res<LevelAssets>,
+    levels: Res<Assets<Level>>,
+    fish_assets: Res<FishAssets>,
+    overrides: Res<SpawnOverrides>,
+    mut pond_rng: ResMut<PondRng>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(CurrentLevel {
+        handle: level_assets.level.clone(),
+        elapsed_seconds: 0.0,
+        fish_collected: 0,
+        outcome: None,
+    });
+
+    let level = match levels.get(&level_assets.level) {
+        Some(level) => level,
+        None => return,
+    };
+
+    for transform in level.npc_transforms(&overrides, pond_rng.rng()) {
+        commands.spawn((Fish::new_npc(transform, &fish_assets), Session));
+    }
+
+    commands.spawn((
+        Fish::new_player(level.player_transform(), &fish_assets),
+        Session,
+    ));
+
+    spawn_obstacles(&mut commands, level, Some((&mut *meshes, &mut *materials)));
+}
+
+/// Spawns the level's obstacles, drawn only when `render_assets` are given so
+/// headless ponds can still collide with them.
+pub fn spawn_obstacles(
+    commands: &mut Commands,
+    level: &Level,
+    render_assets: Option<(&mut Assets<Mesh>, &mut Assets<StandardMaterial>)>,
+) {
+    match render_assets {
+        Some((meshes, materials)) => {
+            let obstacle_material = materials.add(StandardMaterial {
+                base_color: Color::rgb(0.35, 0.3, 0.25),
+                perceptual_roughness: 1.0,
+                ..default()
+            });
+
+            for obstacle in level.obstacles.iter() {
+                commands.spawn((
+                    PbrBundle {
+                        mesh: meshes.add(Mesh::from(shape::Icosphere {
+                            radius: obstacle.radius,
+                            subdivisions: 2,
+                        })),
+                        material: obstacle_material.clone(),
+                        transform: Transform::from_translation(
+                            Vec2::from(obstacle.center).extend(0.0),
+                        ),
+                        ..default()
+                    },
+                    *obstacle,
+                    Session,
+                ));
+            }
+        }
+        None => {
+            for obstacle in level.obstacles.iter() {
+                commands.spawn((
+                    TransformBundle::from_transform(Transform::from_translation(
+                        Vec2::from(obstacle.center).extend(0.0),
+                    )),
+                    *obstacle,
+                    Session,
+                ));
+            }
+        }
+    }
+}
+
+fn pond_bounds_system(
+    current_level: Option<Res<CurrentLevel>>,
+    levels: Res<Assets<Level>>,
+    mut moveables: Query<&mut Transform, With<Moveable>>,
+) {
+    let level = match current_level.and_then(|current| levels.get(&current.handle)) {
+        Some(level) => level,
+        None => return,
+    };
+
+    moveables.for_each_mut(|mut transform| {
+        let position = transform.translation.truncate();
+        let clamped = level.pond.clamp(position);
+        if clamped != position {
+            transform.translation.x = clamped.x;
+            transform.translation.y = clamped.y;
+        }
+    });
+}
+
+pub fn obstacle_collision_system(
+    obstacles: Query<&Obstacle>,
+    mut moveables: Query<&mut Transform, With<Moveable>>,
+) {
+    obstacles.for_each(|obstacle| {
+        let center = Vec2::from(obstacle.center);
+        moveables.for_each_mut(|mut transform| {
+            let delta = transform.translation.truncate() - center;
+            let distance = delta.length();
+            if distance < obstacle.radius && distance > f32::EPSILON {
+                let pushed = center + delta * (obstacle.radius / distance);
+                transform.translation.x = pushed.x;
+                transform.translation.y = pushed.y;
+            }
+        });
+    });
+}
+
+fn level_objectives_system(
+    time: Res<Time>,
+    current_level: Option<ResMut<CurrentLevel>>,
+    levels: Res<Assets<Level>>,
+    mut ev_joined: EventReader<JoinedPlayerEvent>,
+    groupables: Query<(&Transform, &Groupable)>,
+    mut ev_complete: EventWriter<LevelCompleteEvent>,
+) {
+    let joined = ev_joined.iter().count();
+
+    let mut current_level = match current_level {
+        Some(current_level) if current_level.outcome.is_none() => current_level,
+        _ => return,
+    };
+    let level = match levels.get(&current_level.handle) {
+        Some(level) => level,
+        None => return,
+    };
+
+    current_level.elapsed_seconds += time.delta_seconds();
+    current_level.fish_collected += joined;
+
+    let won = match level.win {
+        WinCondition::GatherFish { count } => current_level.fish_collected >= count,
+        WinCondition::ReachGoal { center, radius } => {
+            let (position_summed, count) = groupables
+                .iter()
+                .filter(|(_, groupable)| groupable.is_grouped_with_player())
+                .fold((Vec2::ZERO, 0), |(sum, count), (transform, _)| {
+                    (sum + transform.translation.truncate(), count + 1)
+                });
+
+            count > 0
+                && ((1.0 / count as f32) * position_summed).distance(Vec2::from(center)) <= radius
+        }
+    };
+
+    let outcome = if won {
+        Some(LevelOutcome::Won)
+    } else {
+        level.lose.iter().find_map(|condition| match *condition {
+            LoseCondition::TimeLimit { seconds } if current_level.elapsed_seconds >= seconds => {
+                Some(LevelOutcome::Lost("Out of time".to_string()))
+            }
+            LoseCondition::RivalGroupSize { size } if stats.largest_rival_group >= size => Some(
+                LevelOutcome::Lost(format!("A rival school reached {} fish", size)),
+            ),
+            _ => None,
+        })
+    };
+
+    if let Some(outcome) = outcome {
+        current_level.outcome = Some(outcome.clone());
+        ev_complete.send(LevelCompleteEvent(outcome));
+    }
+}
@@ -1,6 +1,56 @@
+use bevy::{app::PluginGroupBuilder, prelude::*};
+
+pub mod animation;
+pub mod audio;
 pub mod camera;
+pub mod environment;
 pub mod fishes;
 pub mod forces;
+pub mod genetics;
+pub mod group_rings;
 pub mod groups;
+pub mod headless;
+pub mod hud;
 pub mod input;
+pub mod levels;
+pub mod lifecycle;
+pub mod menus;
+pub mod minimap;
+pub mod options;
+pub mod particles;
+pub mod persistence;
 pub mod random;
+pub mod states;
+pub mod variety;
+
+/// Every HomeKoi plugin. Expects a [`states::GameState`] state to be registered and
+/// the asset collections used by the plugins to be loaded during
+/// [`states::GameState::AssetLoading`]. Any member but [`states::StatesPlugin`],
+/// which registers what the others share, can be left out with
+/// `HomeKoiPlugins.build().disable::<P>()`; the others keep working without it.
+pub struct HomeKoiPlugins;
+
+impl PluginGroup for HomeKoiPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(states::StatesPlugin)
+            .add(forces::ForcesPlugin)
+            .add(groups::GroupsPlugin)
+            .add(fishes::FishPlugin)
+            .add(animation::FishAnimationPlugin)
+            .add(variety::VarietyPlugin)
+            .add(lifecycle::LifecyclePlugin)
+            .add(genetics::GeneticsPlugin)
+            .add(camera::CameraPlugin)
+            .add(input::InputPlugin)
+            .add(audio::AudioCuePlugin)
+            .add(levels::LevelPlugin)
+            .add(environment::PondEnvironmentPlugin)
+            .add(particles::ParticlePlugin)
+            .add(hud::HudPlugin)
+            .add(minimap::MinimapPlugin)
+            .add(group_rings::GroupRingsPlugin)
+            .add(menus::MenusPlugin)
+            .add(persistence::PersistencePlugin)
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{Collider, RapierContext, Sensor};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::{CameraFocus, CameraTarget, TemporaryCameraTarget},
+    fishes::{Fish, FishAssets},
+    groups::Groupable,
+    levels::{CurrentLevel, Level},
+    random::{random_direction, PondRng},
+    states::{GameState, Session},
+    variety::{KoiSize, KoiVariety},
+};
+
+/// Scale of a newly hatched fish relative to its adult size.
+const JUVENILE_SCALE: f32 = 0.3;
+
+pub struct LifecyclePlugin;
+
+impl Plugin for LifecyclePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LifecycleSettings>()
+            .init_resource::<Courtships>()
+            .register_type::<Age>()
+            .register_type::<Growth>()
+            .register_type::<Food>()
+            .add_startup_system(setup_food_assets)
+            .add_system_set(
+                SystemSet::on_update(GameState::Running)
+                    .with_system(assign_lifecycle_system)
+                    .with_system(age_system)
+                    .with_system(spawn_food_system)
+                    .with_system(food_collider_system)
+                    .with_system(eat_food_system)
+                    .with_system(breeding_system),
+            );
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct LifecycleSettings {
+    /// Seconds between food drops.
+    pub food_interval: f32,
+    /// Food drops stop while this many pellets are in the pond.
+    pub max_food: usize,
+    /// Food drops land within this distance of the pond's center.
+    pub food_radius: f32,
+    /// How close a fish needs to get to a pellet to eat it. Only fish that are
+    /// still growing eat.
+    pub eat_distance: f32,
+    /// Growth progress gained per pellet; a juvenile becomes an adult at 1.
+    pub growth_per_food: f32,
+    /// Youngest age, in seconds, at which adults breed.
+    pub min_breeding_age: f32,
+    /// Seconds two adults of the same group must stay close to breed.
+    pub courtship_time: f32,
+    /// Seconds after breeding before a fish can breed again.
+    pub breeding_cooldown: f32,
+    /// Breeding stops while this many fish are in the pond.
+    pub max_population: usize,
+    /// How newly dropped food pulls the camera, if at all.
+    pub food_camera_focus: Option<CameraFocus>,
+    /// How fish born into the player's school pull the camera, if at all.
+    pub birth_camera_focus: Option<CameraFocus>,
+}
+
+impl Default for LifecycleSettings {
+    fn default() -> Self {
+        Self {
+            food_interval: 2.0,
+            max_food: 30,
+            food_radius: 800.0,
+            eat_distance: 3.0,
+            growth_per_food: 0.25,
+            min_breeding_age: 30.0,
+            courtship_time: 5.0,
+            breeding_cooldown: 60.0,
+            max_population: 500,
+            // Only widens the view, and is the first thing left out when it can't fit.
+            food_camera_focus: Some(CameraFocus {
+                target: CameraTarget {
+                    weight: 0.0,
+                    padding: 5.0,
+                    priority: -1,
+                },
+                seconds: 3.0,
+            }),
+            birth_camera_focus: Some(CameraFocus {
+                target: CameraTarget {
+                    weight: 0.5,
+                    padding: 5.0,
+                    priority: 0,
+                },
+                seconds: 2.0,
+            }),
+        }
+    }
+}
+
+#[derive(Component, Default, Reflect, Serialize, Deserialize, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct Age {
+    pub seconds: f32,
+}
+
+#[derive(Component, Reflect, Serialize, Deserialize, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct Growth {
+    /// From 0 for a hatchling to 1 for an adult.
+    pub progress: f32,
+    /// Seconds until this fish can breed again.
+    pub breeding_cooldown: f32,
+}
+
+impl Default for Growth {
+    fn default() -> Self {
+        Self::adult()
+    }
+}
+
+impl Growth {
+    pub fn juvenile() -> Self {
+        Self {
+            progress: 0.0,
+            breeding_cooldown: 0.0,
+        }
+    }
+
+    pub fn adult() -> Self {
+        Self {
+            progress: 1.0,
+            breeding_cooldown: 0.0,
+        }
+    }
+
+    pub fn is_adult(&self) -> bool {
+        self.progress >= 1.0
+    }
+
+    /// Scale of the fish relative to its adult [`KoiSize`].
+    pub fn scale(&self) -> f32 {
+        JUVENILE_SCALE + (1.0 - JUVENILE_SCALE) * self.progress.clamp(0.0, 1.0)
+    }
+}
+
+/// A pellet that fish grow by eating.
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Food;
+
+/// Sent when two fish breed; `child` has just been spawned.
+pub struct FishBornEvent {
+    pub parents: [Entity; 2],
+    pub child: Entity,
+}
+
+#[derive(Resource)]
+struct FoodAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+/// Seconds each pair of adults has spent close to one another.
+#[derive(Resource, Default)]
+struct Courtships {
+    by_pair: HashMap<(Entity, Entity), f32>,
+}
+
+/// Food is only drawn when rendering is available; headless ponds still get pellets.
+fn setup_food_assets(
+    mut commands: Commands,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    materials: Option<ResMut<Assets<StandardMaterial>>>,
+) {
+    if let (Some(mut meshes), Some(mut materials)) = (meshes, materials) {
+        commands.insert_resource(FoodAssets {
+            mesh: meshes.add(Mesh::from(shape::Icosphere {
+                radius: 0.5,
+                subdivisions: 1,
+            })),
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgb(0.6, 0.4, 0.2),
+                perceptual_roughness: 1.0,
+                ..default()
+            }),
+        });
+    }
+}
+
+/// Fish spawned without a lifecycle start out as adults.
+fn assign_lifecycle_system(
+    mut commands: Commands,
+    fishes: Query<Entity, (With<Fish>, Without<Growth>)>,
+) {
+    fishes.for_each(|entity| {
+        commands
+            .entity(entity)
+            .insert((Age::default(), Growth::adult()));
+    });
+}
+
+fn age_system(time: Res<Time>, mut fishes: Query<(&mut Age, &mut Growth)>) {
+    let delta = time.delta_seconds();
+    fishes.for_each_mut(|(mut age, mut growth)| {
+        age.seconds += delta;
+        if growth.breeding_cooldown > 0.0 {
+            growth.breeding_cooldown = (growth.breeding_cooldown - delta).max(0.0);
+        }
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_food_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<LifecycleSettings>,
+    food_assets: Option<Res<FoodAssets>>,
+    current_level: Option<Res<CurrentLevel>>,
+    levels: Option<Res<Assets<Level>>>,
+    mut pond_rng: ResMut<PondRng>,
+    mut since_last_drop: Local<f32>,
+    food: Query<(), With<Food>>,
+) {
+    *since_last_drop += time.delta_seconds();
+    if *since_last_drop < settings.food_interval || food.iter().count() >= settings.max_food {
+        return;
+    }
+    *since_last_drop = 0.0;
+
+    let rng = pond_rng.rng();
+    let length: f32 = settings.food_radius * rng.gen::<f32>();
+    let mut position = length * random_direction(rng).truncate();
+    if let Some(level) = current_level
+        .zip(levels.as_ref())
+        .and_then(|(current, levels)| levels.get(&current.handle))
+    {
+        position = level.pond.clamp(position);
+    }
+    let transform = Transform::from_translation(position.extend(0.0));
+
+    let mut pellet = match food_assets {
+        Some(food_assets) => commands.spawn((
+            PbrBundle {
+                mesh: food_assets.mesh.clone(),
+                material: food_assets.material.clone(),
+                transform,
+                ..default()
+            },
+            Food,
+            Session,
+        )),
+        None => commands.spawn((TransformBundle::from_transform(transform), Food, Session)),
+    };
+    if let Some(focus) = settings.food_camera_focus {
+        pellet.insert(TemporaryCameraTarget::from(focus));
+    }
+}
+
+/// Pellets are found through their sensors' intersections, the way the flocking
+/// and breeding systems find nearby fish.
+fn food_collider_system(
+    mut commands: Commands,
+    settings: Res<LifecycleSettings>,
+    food: Query<Entity, (With<Food>, Without<Collider>)>,
+) {
+    food.for_each(|pellet| {
+        commands
+            .entity(pellet)
+            .insert((Collider::ball(settings.eat_distance), Sensor));
+    });
+}
+
+/// Growing fish eat pellets they touch; adults leave them for the juveniles.
+fn eat_food_system(
+    mut commands: Commands,
+    settings: Res<LifecycleSettings>,
+    rapier: Res<RapierContext>,
+    food: Query<&Transform, With<Food>>,
+    mut fishes: Query<(&Transform, &mut Growth), Without<Food>>,
+) {
+    let mut eaten = HashSet::new();
+    for (e1, e2, intersecting) in rapier.intersection_pairs() {
+        let (pellet, fish) = if food.contains(e1) {
+            (e1, e2)
+        } else {
+            (e2, e1)
+        };
+        if !intersecting || eaten.contains(&pellet) {
+            continue;
+        }
+        let (pellet_transform, (transform, mut growth)) =
+            match (food.get(pellet), fishes.get_mut(fish)) {
+                (Ok(pellet_transform), Ok(fish)) => (pellet_transform, fish),
+                _ => continue,
+            };
+
+        // The fish's sensor is larger than its body, so the pellet has to be in reach too.
+        if growth.is_adult()
+            || transform.translation.distance(pellet_transform.translation) > settings.eat_distance
+        {
+            continue;
+        }
+
+        growth.progress = (growth.progress + settings.growth_per_food).min(1.0);
+        eaten.insert(pellet);
+        commands.entity(pellet).despawn_recursive();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn breeding_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<LifecycleSettings>,
+    rapier: Res<RapierContext>,
+    fish_assets: Option<Res<FishAssets>>,
+    mut courtships: ResMut<Courtships>,
+    mut pond_rng: ResMut<PondRng>,
+    mut ev_born: EventWriter<FishBornEvent>,
+    mut fishes: Query<
+        (
+            &Transform,
+            &Groupable,
+            &Age,
+            &mut Growth,
+            Option<&KoiVariety>,
+            Option<&KoiSize>,
+        ),
+        With<Fish>,
+    >,
+) {
+    let can_breed = |age: &Age, growth: &Growth| {
+        growth.is_adult()
+            && growth.breeding_cooldown <= 0.0
+            && age.seconds >= settings.min_breeding_age
+    };
+
+    let mut population = fishes.iter().count();
+    let mut courting = HashMap::new();
+    for (e1, e2, _) in rapier.intersection_pairs() {
+        let pair = if e1 < e2 { (e1, e2) } else { (e2, e1) };
+        let (f1, f2) = match (fishes.get(pair.0), fishes.get(pair.1)) {
+            (Ok(f1), Ok(f2)) => (f1, f2),
+            _ => continue,
+        };
+
+        let same_group = f1.1.id.is_some() && f1.1.id == f2.1.id;
+        if pair.0 == pair.1 || !same_group || !can_breed(f1.2, &f1.3) || !can_breed(f2.2, &f2.3) {
+            continue;
+        }
+
+        let courted =
+            courtships.by_pair.get(&pair).copied().unwrap_or_default() + time.delta_seconds();
+        if courted < settings.courtship_time || population >= settings.max_population {
+            courting.insert(pair, courted);
+            continue;
+        }
+
+        let rng = pond_rng.rng();
+        let variety = KoiVariety::cross(
+            f1.4.copied().unwrap_or_default(),
+            f2.4.copied().unwrap_or_default(),
+            rng,
+        );
+        let size = KoiSize(
+            0.5 * (f1.5.copied().unwrap_or_default().0 + f2.5.copied().unwrap_or_default().0)
+                * (0.95 + 0.1 * rng.gen::<f32>()),
+        );
+        let translation = 0.5 * (f1.0.translation + f2.0.translation);
+        let transform = Transform::from_translation(translation)
+            .looking_at(translation + random_direction(rng), Vec3::Z);
+        let group = Groupable { id: f1.1.id };
+
+        let mut child = match &fish_assets {
+            Some(fish_assets) => commands.spawn((Fish::new_npc(transform, fish_assets), Session)),
+            None => commands.spawn((Fish::new_headless_npc(transform), Session)),
+        };
+        let joins_player = group.is_grouped_with_player();
+        child.insert((group, variety, size, Age::default(), Growth::juvenile()));
+        if let Some(focus) = settings.birth_camera_focus.filter(|_| joins_player) {
+            child.insert(TemporaryCameraTarget::from(focus));
+        }
+        let child = child.id();
+
+        for parent in [pair.0, pair.1] {
+            if let Ok((_, _, _, mut growth, _, _)) = fishes.get_mut(parent) {
+                growth.breeding_cooldown = settings.breeding_cooldown;
+            }
+        }
+
+        population += 1;
+        ev_born.send(FishBornEvent {
+            parents: [pair.0, pair.1],
+            child,
+        });
+    }
+
+    // Pairs that drifted apart or bred start over.
+    courtships.by_pair = courting;
+}
@@ -1,45 +1,230 @@
-use bevy::{core_pipeline::clear_color::ClearColorConfig, prelude::*};
+use std::{collections::HashMap, time::Instant};
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerSettings},
+    asset::FileAssetIo,
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    utils::Duration,
+    window::WindowMode,
+};
 use bevy_asset_loader::prelude::*;
 
 use bevy_rapier3d::prelude::{NoUserData, RapierPhysicsPlugin};
 use homekoi::{
-    camera::*, fishes::*, forces::ForcesPlugin, groups::GroupsPlugin, input::click_to_move_system,
-    random::random_direction,
+    audio::{AudioAssets, AudioSettings},
+    camera::TrackingCenterOfMassCamera,
+    fishes::{Fish, FishAssets},
+    forces::Moveable,
+    groups::Groupable,
+    headless::HeadlessSimPlugin,
+    hud::UiAssets,
+    levels::{
+        obstacle_collision_system, register_level_path, spawn_obstacles, Level, LevelAssets,
+        SpawnOverrides, DEFAULT_LEVEL_PATH,
+    },
+    options::LaunchOptions,
+    random::PondRng,
+    states::{GameState, Session},
+    HomeKoiPlugins,
 };
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins)
-        .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
-        .add_plugin(ForcesPlugin)
-        .add_plugin(GroupsPlugin)
-        .add_system(camera_center_of_mass_track_system)
-        .add_system(fish_track_system)
-        .add_system(click_to_move_system)
+    let options = match LaunchOptions::load() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("Failed to read config file: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if options.headless.unwrap_or(false) {
+        run_headless(&options);
+    } else {
+        run_windowed(&options);
+    }
+}
+
+const HEADLESS_TIMESTEP: f32 = 1.0 / 60.0;
+/// One simulated minute.
+const HEADLESS_STEPS: u64 = 3600;
+
+fn run_windowed(options: &LaunchOptions) {
+    let mut app = App::new();
+
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        window: WindowDescriptor {
+            title: "HomeKoi".to_string(),
+            mode: if options.fullscreen.unwrap_or(false) {
+                WindowMode::BorderlessFullscreen
+            } else {
+                WindowMode::Windowed
+            },
+            ..default()
+        },
+        ..default()
+    }))
+    .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+    .insert_resource(SpawnOverrides {
+        fish_count: options.fish_count,
+        spawn_radius: options.spawn_radius,
+    });
+
+    if let Some(seed) = options.seed {
+        app.insert_resource(PondRng::new(seed));
+    }
+
+    app.add_plugins(HomeKoiPlugins);
+
+    // Runs after the saved settings are loaded and only applies to this run; it
+    // isn't saved unless the player changes a setting during it.
+    if let Some(muted) = options.mute {
+        app.add_startup_system_to_stage(
+            StartupStage::PostStartup,
+            move |mut settings: ResMut<AudioSettings>| settings.muted = muted,
+        );
+    }
+
+    app.add_startup_system(setup_camera)
         .add_loading_state(
             LoadingState::new(GameState::AssetLoading)
-                .continue_to_state(GameState::Running)
-                .with_collection::<FishAssets>(),
+                .continue_to_state(GameState::MainMenu)
+                .with_collection::<FishAssets>()
+                .with_collection::<AudioAssets>()
+                .with_collection::<UiAssets>()
+                .with_collection::<LevelAssets>(),
         )
         .add_state(GameState::AssetLoading)
-        .add_system_set(SystemSet::on_enter(GameState::Running).with_system(setup_scene))
-        .add_system_set(SystemSet::on_update(GameState::Running).with_system(fish_animator_system))
-        .add_system_set(
-            SystemSet::on_update(GameState::Running).with_system(fish_joined_player_cue_system),
-        )
+        .add_system_set(SystemSet::on_enter(GameState::Running).with_system(setup_scene));
+
+    register_level_path(
+        &mut app,
+        options.level.as_deref().unwrap_or(DEFAULT_LEVEL_PATH),
+    );
+
+    app.run();
+}
+
+fn run_headless(options: &LaunchOptions) {
+    let path = FileAssetIo::get_base_path()
+        .join("assets")
+        .join(options.level.as_deref().unwrap_or(DEFAULT_LEVEL_PATH));
+    let level = match Level::load(&path) {
+        Ok(level) => level,
+        Err(e) => {
+            eprintln!("Failed to read level {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    };
+
+    // Time advances by a fixed step per update, so the run can go as fast as it can.
+    App::new()
+        .insert_resource(ScheduleRunnerSettings::run_loop(Duration::ZERO))
+        .insert_resource(SpawnOverrides {
+            fish_count: options.fish_count,
+            spawn_radius: options.spawn_radius,
+        })
+        .insert_resource(HeadlessRun {
+            level,
+            steps: options.steps.unwrap_or(HEADLESS_STEPS),
+            step: 0,
+            started: Instant::now(),
+        })
+        .add_plugins(MinimalPlugins)
+        .add_plugin(HeadlessSimPlugin {
+            timestep: Some(HEADLESS_TIMESTEP),
+            seed: options.seed,
+        })
+        .add_startup_system(spawn_headless_level)
+        .add_system(headless_pond_bounds_system)
+        .add_system(obstacle_collision_system)
+        .add_system_to_stage(CoreStage::Last, headless_summary_system)
         .run();
 }
 
-const FISH_TO_SPAWN: usize = 100;
-const SPAWN_RADIUS: f32 = 1000.0;
+/// The level a headless run plays and how far along it is.
+#[derive(Resource)]
+struct HeadlessRun {
+    level: Level,
+    steps: u64,
+    step: u64,
+    started: Instant,
+}
+
+fn spawn_headless_level(
+    mut commands: Commands,
+    run: Res<HeadlessRun>,
+    overrides: Res<SpawnOverrides>,
+    mut pond_rng: ResMut<PondRng>,
+) {
+    for transform in run.level.npc_transforms(&overrides, pond_rng.rng()) {
+        commands.spawn(Fish::new_headless_npc(transform));
+    }
+
+    commands.spawn(Fish::new_headless_player(run.level.player_transform()));
+
+    spawn_obstacles(&mut commands, &run.level, None);
+}
+
+fn headless_pond_bounds_system(
+    run: Res<HeadlessRun>,
+    mut moveables: Query<&mut Transform, With<Moveable>>,
+) {
+    moveables.for_each_mut(|mut transform| {
+        let position = transform.translation.truncate();
+        let clamped = run.level.pond.clamp(position);
+        if clamped != position {
+            transform.translation.x = clamped.x;
+            transform.translation.y = clamped.y;
+        }
+    });
+}
+
+/// Stops the run after its last step and prints how the pond ended up.
+fn headless_summary_system(
+    time: Res<Time>,
+    mut run: ResMut<HeadlessRun>,
+    pond_rng: Res<PondRng>,
+    groupables: Query<&Groupable>,
+    mut ev_exit: EventWriter<AppExit>,
+) {
+    run.step += 1;
+    if run.step < run.steps {
+        return;
+    }
+
+    let mut group_sizes: HashMap<usize, usize> = HashMap::new();
+    let mut ungrouped = 0;
+    groupables.for_each(|groupable| match groupable.id {
+        Some(id) => *group_sizes.entry(id).or_default() += 1,
+        None => ungrouped += 1,
+    });
+    let school = group_sizes.remove(&0).unwrap_or(0);
+
+    println!("Level: {}", run.level.name);
+    println!("Seed: {}", pond_rng.seed());
+    println!(
+        "Steps: {} ({:.1}s simulated in {:.1}s)",
+        run.step,
+        time.elapsed_seconds(),
+        run.started.elapsed().as_secs_f32()
+    );
+    println!(
+        "Fish: {}",
+        school + ungrouped + group_sizes.values().sum::<usize>()
+    );
+    println!("Player's school: {}", school);
+    println!(
+        "Other groups: {} (largest {})",
+        group_sizes.len(),
+        group_sizes.values().max().copied().unwrap_or(0)
+    );
+    println!("Ungrouped: {}", ungrouped);
 
-#[derive(Clone, Eq, PartialEq, Debug, Hash)]
-enum GameState {
-    AssetLoading,
-    Running,
+    ev_exit.send(AppExit);
 }
 
-fn setup_scene(mut commands: Commands, fish_assets: Res<FishAssets>, audio: Res<Audio>) {
+fn setup_camera(mut commands: Commands) {
     commands.spawn((
         Camera3dBundle {
             camera_3d: Camera3d {
@@ -56,35 +241,23 @@ fn setup_scene(mut commands: Commands, fish_assets: Res<FishAssets>, audio: Res<
         },
         TrackingCenterOfMassCamera,
     ));
+}
 
+fn setup_scene(mut commands: Commands) {
     commands.insert_resource(AmbientLight {
         color: Color::WHITE,
         brightness: 1.0,
     });
 
-    commands.spawn(DirectionalLightBundle {
-        directional_light: DirectionalLight {
-            illuminance: 75000.0,
+    commands.spawn((
+        DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                illuminance: 75000.0,
+                ..default()
+            },
+            transform: Transform::from_xyz(10.0, -10.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
             ..default()
         },
-        transform: Transform::from_xyz(10.0, -10.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
-        ..default()
-    });
-
-    for _ in 0..FISH_TO_SPAWN {
-        let length: f32 = SPAWN_RADIUS * rand::random::<f32>();
-        let translation = length * random_direction();
-        let direction = random_direction();
-
-        let transform =
-            Transform::from_translation(translation).looking_at(translation + direction, Vec3::Z);
-        commands.spawn(Fish::new_npc(transform, &fish_assets));
-    }
-
-    commands.spawn(Fish::new_player(
-        Transform::from_translation(Vec3::ZERO).looking_at(Vec3::Y, Vec3::Z),
-        &fish_assets,
+        Session,
     ));
-
-    fish_assets.start_background_music(audio);
 }
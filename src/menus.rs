@@ -0,0 +1,236 @@
+use bevy::prelude::*;
+
+use crate::{
+    audio::AudioSettings,
+    groups::Groupable,
+    hud::{HudStats, UiAssets},
+    levels::{CurrentLevel, LevelCompleteEvent, LevelOutcome},
+    states::{despawn_session_system, GameState},
+};
+
+const MENU_FONT_SIZE: f32 = 36.0;
+const MENU_BACKGROUND: Color = Color::rgba(0.0, 0.1, 0.15, 0.6);
+
+pub struct MenusPlugin;
+
+impl Plugin for MenusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(setup_main_menu))
+            .add_system_set(
+                SystemSet::on_update(GameState::MainMenu).with_system(main_menu_input_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::MainMenu).with_system(despawn_screen::<MenuScreen>),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Running)
+                    .with_system(running_input_system)
+                    .with_system(all_fish_collected_system)
+                    .with_system(level_complete_system),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(setup_pause_menu))
+            .add_system_set(
+                SystemSet::on_update(GameState::Paused)
+                    .with_system(pause_menu_input_system)
+                    .with_system(pause_menu_audio_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Paused).with_system(despawn_screen::<MenuScreen>),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Results).with_system(setup_results_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Results).with_system(results_input_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Results)
+                    .with_system(despawn_screen::<MenuScreen>)
+                    .with_system(despawn_session_system),
+            );
+    }
+}
+
+#[derive(Component)]
+struct MenuScreen;
+
+fn spawn_screen(commands: &mut Commands, ui_assets: &UiAssets, text: String) -> Entity {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    position_type: PositionType::Absolute,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BackgroundColor(MENU_BACKGROUND),
+                ..default()
+            },
+            MenuScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    text,
+                    TextStyle {
+                        font: ui_assets.font.clone(),
+                        font_size: MENU_FONT_SIZE,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_text_alignment(TextAlignment::CENTER),
+            );
+        })
+        .id()
+}
+
+fn despawn_screen<T: Component>(mut commands: Commands, screens: Query<Entity, With<T>>) {
+    screens.for_each(|entity| commands.entity(entity).despawn_recursive());
+}
+
+fn setup_main_menu(mut commands: Commands, ui_assets: Res<UiAssets>) {
+    spawn_screen(
+        &mut commands,
+        &ui_assets,
+        "HomeKoi\n\nPress Enter to start".to_string(),
+    );
+}
+
+fn main_menu_input_system(mut keys: ResMut<Input<KeyCode>>, mut state: ResMut<State<GameState>>) {
+    if keys.just_pressed(KeyCode::Return) {
+        keys.reset(KeyCode::Return);
+        let _ = state.set(GameState::Running);
+    }
+}
+
+fn running_input_system(mut keys: ResMut<Input<KeyCode>>, mut state: ResMut<State<GameState>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        keys.reset(KeyCode::Escape);
+        let _ = state.push(GameState::Paused);
+    }
+}
+
+fn all_fish_collected_system(
+    mut state: ResMut<State<GameState>>,
+    changed: Query<(), Changed<Groupable>>,
+    groupables: Query<&Groupable>,
+) {
+    if changed.is_empty() {
+        return;
+    }
+
+    let mut groupables = groupables.iter().peekable();
+    if groupables.peek().is_some() && groupables.all(Groupable::is_grouped_with_player) {
+        let _ = state.set(GameState::Results);
+    }
+}
+
+fn level_complete_system(
+    mut state: ResMut<State<GameState>>,
+    mut ev_complete: EventReader<LevelCompleteEvent>,
+) {
+    if ev_complete.iter().next().is_some() {
+        let _ = state.set(GameState::Results);
+    }
+}
+
+fn setup_pause_menu(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    audio_settings: Option<Res<AudioSettings>>,
+) {
+    spawn_pause_menu(&mut commands, &ui_assets, audio_settings.as_deref());
+}
+
+/// The audio settings a pause menu was drawn with.
+#[derive(Component)]
+struct ShownAudioSettings(Option<AudioSettings>);
+
+fn spawn_pause_menu(
+    commands: &mut Commands,
+    ui_assets: &UiAssets,
+    audio_settings: Option<&AudioSettings>,
+) {
+    let mut text = "Paused\n\nEsc to resume\nQ to end the session".to_string();
+    if let Some(settings) = audio_settings {
+        text += &format!(
+            "\n\nVolume {:.0}%  Music {:.0}%  Effects {:.0}%{}\nM to mute, -/= volume, [/] music, ;/' effects",
+            100.0 * settings.master_volume,
+            100.0 * settings.music_volume,
+            100.0 * settings.sfx_volume,
+            if settings.muted { "  (muted)" } else { "" },
+        );
+    }
+
+    let screen = spawn_screen(commands, ui_assets, text);
+    commands
+        .entity(screen)
+        .insert(ShownAudioSettings(audio_settings.copied()));
+}
+
+/// Redraws the pause menu when the audio settings change while it's open. Changes
+/// made before pausing are already on the menu, so it isn't rebuilt on entry.
+fn pause_menu_audio_system(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    audio_settings: Option<Res<AudioSettings>>,
+    screens: Query<(Entity, &ShownAudioSettings)>,
+) {
+    let settings = match audio_settings.filter(|settings| settings.is_changed()) {
+        Some(settings) => settings,
+        None => return,
+    };
+
+    for (entity, shown) in screens.iter() {
+        if shown.0.as_ref() != Some(&*settings) {
+            commands.entity(entity).despawn_recursive();
+            spawn_pause_menu(&mut commands, &ui_assets, Some(&*settings));
+        }
+    }
+}
+
+fn pause_menu_input_system(mut keys: ResMut<Input<KeyCode>>, mut state: ResMut<State<GameState>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        keys.reset(KeyCode::Escape);
+        let _ = state.pop();
+    } else if keys.just_pressed(KeyCode::Q) {
+        keys.reset(KeyCode::Q);
+        let _ = state.replace(GameState::Results);
+    }
+}
+
+fn setup_results_screen(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    stats: Option<Res<HudStats>>,
+    current_level: Option<Res<CurrentLevel>>,
+) {
+    let mut text = match current_level.and_then(|current| current.outcome.clone()) {
+        Some(LevelOutcome::Won) => "Level complete!".to_string(),
+        Some(LevelOutcome::Lost(reason)) => format!("Level failed: {}", reason),
+        None => "Session over".to_string(),
+    };
+    // The stats are only kept while the HUD plugin is enabled.
+    if let Some(stats) = stats {
+        let elapsed = stats.elapsed_seconds as u32;
+        text += &format!(
+            "\n\nSchool size: {}\nFish collected: {}\nTime: {}:{:02}\nLargest rival: {}",
+            stats.school_size,
+            stats.fish_collected,
+            elapsed / 60,
+            elapsed % 60,
+            stats.largest_rival_group,
+        );
+    }
+    text += "\n\nPress Enter to return to the menu";
+    spawn_screen(&mut commands, &ui_assets, text);
+}
+
+fn results_input_system(mut keys: ResMut<Input<KeyCode>>, mut state: ResMut<State<GameState>>) {
+    if keys.just_pressed(KeyCode::Return) {
+        keys.reset(KeyCode::Return);
+        let _ = state.set(GameState::MainMenu);
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    render::{
+        camera::{RenderTarget, ScalingMode},
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        view::RenderLayers,
+    },
+};
+
+use crate::{
+    groups::Groupable,
+    levels::{Level, LevelAssets},
+    states::GameState,
+};
+
+const MINIMAP_LAYER: u8 = 1;
+const MINIMAP_CAMERA_HEIGHT: f32 = 100.0;
+const MINIMAP_MARKER_SCALE: f32 = 1.0 / 60.0;
+const MINIMAP_MARGIN: f32 = 1.05;
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MinimapSettings>()
+            .init_resource::<MinimapMaterials>()
+            .add_system_set(SystemSet::on_exit(GameState::AssetLoading).with_system(setup_minimap))
+            .add_system(spawn_minimap_markers_system)
+            .add_system(minimap_marker_color_system)
+            .add_system(minimap_marker_system);
+    }
+}
+
+#[derive(Resource)]
+pub struct MinimapSettings {
+    /// Radius of the pond shown when no level is loaded; otherwise the level's pond
+    /// fills the minimap.
+    pub pond_radius: f32,
+    /// Width and height of the minimap on screen, in logical pixels.
+    pub size: f32,
+}
+
+impl Default for MinimapSettings {
+    fn default() -> Self {
+        Self {
+            pond_radius: 1000.0,
+            size: 200.0,
+        }
+    }
+}
+
+/// One unlit material per group id, shared by every marker in that group.
+#[derive(Resource, Default)]
+struct MinimapMaterials {
+    marker_mesh: Handle<Mesh>,
+    by_group: HashMap<Option<usize>, Handle<StandardMaterial>>,
+}
+
+impl MinimapMaterials {
+    fn get_or_add(
+        &mut self,
+        group_id: Option<usize>,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Handle<StandardMaterial> {
+        self.by_group
+            .entry(group_id)
+            .or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    base_color: group_color(group_id),
+                    unlit: true,
+                    ..default()
+                })
+            })
+            .clone()
+    }
+}
+
+/// Links a groupable entity to the marker drawn for it on the minimap.
+#[derive(Component)]
+pub struct MinimapMarker(pub Entity);
+
+/// A minimap marker drawn for `entity`. Markers aren't children of what they mark
+/// so its rotation, scale and scene materials leave them alone.
+#[derive(Component)]
+pub struct MinimapMarkerOf {
+    pub entity: Entity,
+}
+
+pub fn group_color(group_id: Option<usize>) -> Color {
+    match group_id {
+        Some(0) => Color::WHITE,
+        Some(id) => Color::hsl((id as f32 * 137.508) % 360.0, 0.75, 0.55),
+        None => Color::GRAY,
+    }
+}
+
+fn setup_minimap(
+    mut commands: Commands,
+    settings: Res<MinimapSettings>,
+    level_assets: Option<Res<LevelAssets>>,
+    levels: Option<Res<Assets<Level>>>,
+    mut minimap_materials: ResMut<MinimapMaterials>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let pond_radius = level_assets
+        .zip(levels)
+        .and_then(|(level_assets, levels)| {
+            levels
+                .get(&level_assets.level)
+                .map(|level| level.pond.half_extent())
+        })
+        .unwrap_or(settings.pond_radius);
+
+    let size = Extent3d {
+        width: settings.size as u32,
+        height: settings.size as u32,
+        ..default()
+    };
+
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+    let image = images.add(image);
+
+    commands.spawn((
+        Camera3dBundle {
+            camera_3d: Camera3d {
+                clear_color: ClearColorConfig::Custom(Color::rgba(0.0, 0.0, 0.0, 0.0)),
+                ..default()
+            },
+            camera: Camera {
+                priority: -1,
+                target: RenderTarget::Image(image.clone()),
+                ..default()
+            },
+            projection: Projection::Orthographic(OrthographicProjection {
+                scaling_mode: ScalingMode::FixedVertical(2.0 * MINIMAP_MARGIN * pond_radius),
+                ..default()
+            }),
+            transform: Transform::from_xyz(0.0, 0.0, MINIMAP_CAMERA_HEIGHT)
+                .looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        UiCameraConfig { show_ui: false },
+        RenderLayers::layer(MINIMAP_LAYER),
+    ));
+
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Circle {
+                radius: pond_radius,
+                vertices: 64,
+            })),
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgba(0.1, 0.3, 0.4, 0.8),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            }),
+            transform: Transform::from_xyz(0.0, 0.0, -MINIMAP_CAMERA_HEIGHT / 2.0),
+            ..default()
+        },
+        RenderLayers::layer(MINIMAP_LAYER),
+    ));
+
+    minimap_materials.marker_mesh = meshes.add(Mesh::from(shape::Icosphere {
+        radius: MINIMAP_MARKER_SCALE * pond_radius,
+        subdivisions: 1,
+    }));
+
+    commands.spawn(ImageBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                right: Val::Px(10.0),
+                top: Val::Px(10.0),
+                ..default()
+            },
+            size: Size::new(Val::Px(settings.size), Val::Px(settings.size)),
+            ..default()
+        },
+        image: UiImage(image),
+        ..default()
+    });
+}
+
+fn spawn_minimap_markers_system(
+    mut commands: Commands,
+    mut minimap_materials: ResMut<MinimapMaterials>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    unmarked: Query<(Entity, &Transform, &Groupable), Without<MinimapMarker>>,
+) {
+    unmarked.for_each(|(entity, transform, groupable)| {
+        let material = minimap_materials.get_or_add(groupable.id, &mut materials);
+        let marker = commands
+            .spawn((
+                PbrBundle {
+                    mesh: minimap_materials.marker_mesh.clone(),
+                    material,
+                    transform: Transform::from_translation(transform.translation),
+                    ..default()
+                },
+                RenderLayers::layer(MINIMAP_LAYER),
+                MinimapMarkerOf { entity },
+            ))
+            .id();
+
+        commands.entity(entity).insert(MinimapMarker(marker));
+    });
+}
+
+/// Keeps each marker on what it marks, and removes markers whose entity is gone.
+fn minimap_marker_system(
+    mut commands: Commands,
+    marked: Query<&Transform, Without<MinimapMarkerOf>>,
+    mut markers: Query<(Entity, &MinimapMarkerOf, &mut Transform)>,
+) {
+    markers.for_each_mut(
+        |(marker, marker_of, mut transform)| match marked.get(marker_of.entity) {
+            Ok(marked_transform) => {
+                if transform.translation != marked_transform.translation {
+                    transform.translation = marked_transform.translation;
+                }
+            }
+            Err(_) => commands.entity(marker).despawn_recursive(),
+        },
+    );
+}
+
+fn minimap_marker_color_system(
+    mut minimap_materials: ResMut<MinimapMaterials>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    groupables: Query<(&Groupable, &MinimapMarker), Changed<Groupable>>,
+    mut markers: Query<&mut Handle<StandardMaterial>>,
+) {
+    groupables.for_each(|(groupable, marker)| {
+        if let Ok(mut material) = markers.get_mut(marker.0) {
+            *material = minimap_materials.get_or_add(groupable.id, &mut materials);
+        }
+    });
+}
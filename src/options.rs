@@ -0,0 +1,84 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use clap::Parser;
+use ron::extensions::Extensions;
+use serde::Deserialize;
+
+/// Launch options for the `homekoi` binary.
+///
+/// Every option can also be set in a RON config file passed with `--config`;
+/// options given on the command line take precedence. Switches take an optional
+/// value, so `--mute=false` turns off a switch the config file turns on.
+#[derive(Parser, Deserialize, Debug, Default, Clone)]
+#[command(about = "A koi pond where you gather a school of fish")]
+#[serde(default)]
+pub struct LaunchOptions {
+    /// Number of NPC fish, replacing the level's spawn areas.
+    #[arg(long)]
+    pub fish_count: Option<usize>,
+    /// Radius around the origin that NPC fish spawn within.
+    #[arg(long)]
+    pub spawn_radius: Option<f32>,
+    /// Seed for the pond's random number generator.
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Level file to play, relative to the assets directory.
+    #[arg(long)]
+    pub level: Option<String>,
+    /// Opens in borderless fullscreen, or windowed with `--fullscreen=false`.
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub fullscreen: Option<bool>,
+    /// Starts with music and sound effects muted, or unmuted with `--mute=false`.
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub mute: Option<bool>,
+    /// Runs only the level's simulation, without a window or audio.
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub headless: Option<bool>,
+    /// Updates the headless simulation runs before printing a summary and exiting.
+    #[arg(long)]
+    pub steps: Option<u64>,
+    /// RON file with default values for any of these options.
+    #[arg(long)]
+    #[serde(skip)]
+    pub config: Option<PathBuf>,
+}
+
+impl LaunchOptions {
+    /// Parses the command line and fills unset options from the config file, if any.
+    pub fn load() -> io::Result<Self> {
+        let options = Self::parse();
+
+        match &options.config {
+            Some(path) => Ok(options.or(Self::from_file(path)?)),
+            None => Ok(options),
+        }
+    }
+
+    /// Reads options from a RON file, where unset options can be left out and set
+    /// ones written without `Some`, e.g. `(fish_count: 500, mute: true)`.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        ron::Options::default()
+            .with_default_extension(Extensions::IMPLICIT_SOME)
+            .from_str(&contents)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Options set in `self`, falling back to `defaults` for the rest.
+    pub fn or(self, defaults: Self) -> Self {
+        Self {
+            fish_count: self.fish_count.or(defaults.fish_count),
+            spawn_radius: self.spawn_radius.or(defaults.spawn_radius),
+            seed: self.seed.or(defaults.seed),
+            level: self.level.or(defaults.level),
+            fullscreen: self.fullscreen.or(defaults.fullscreen),
+            mute: self.mute.or(defaults.mute),
+            headless: self.headless.or(defaults.headless),
+            steps: self.steps.or(defaults.steps),
+            config: self.config,
+        }
+    }
+}
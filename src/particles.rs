@@ -0,0 +1,344 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    fishes::Fish, forces::Moveable, groups::JoinedPlayerEvent, random::random_direction,
+    states::GameState,
+};
+
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ParticleSettings>()
+            .init_resource::<ParticlePool>()
+            .add_event::<EmitParticleEvent>()
+            .add_startup_system(setup_particle_assets)
+            .add_system_set(
+                SystemSet::on_update(GameState::Running)
+                    .with_system(fill_particle_pool_system)
+                    .with_system(wake_system)
+                    .with_system(join_burst_system)
+                    .with_system(
+                        emit_particle_system
+                            .after(fill_particle_pool_system)
+                            .after(wake_system)
+                            .after(join_burst_system),
+                    )
+                    .with_system(particle_system.after(emit_particle_system)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Running).with_system(recall_particles_system),
+            );
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct ParticleSettings {
+    /// Particles alive at once; emitting while all of them are in use does nothing.
+    pub pool_size: usize,
+    /// Fish faster than this leave a wake.
+    pub wake_speed: f32,
+    /// Wake particles per second left by a fish moving at twice `wake_speed`.
+    pub wake_rate: f32,
+    /// Chance of each wake particle being a bubble instead.
+    pub bubble_chance: f32,
+    /// Bubbles released where a fish joins the player's school.
+    pub join_burst: usize,
+    pub wake_lifetime: f32,
+    pub bubble_lifetime: f32,
+    /// Upward acceleration of bubbles.
+    pub buoyancy: f32,
+}
+
+impl Default for ParticleSettings {
+    fn default() -> Self {
+        Self {
+            pool_size: 512,
+            wake_speed: 12.0,
+            wake_rate: 10.0,
+            bubble_chance: 0.2,
+            join_burst: 16,
+            wake_lifetime: 0.8,
+            bubble_lifetime: 1.5,
+            buoyancy: 6.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleKind {
+    Wake,
+    Bubble,
+}
+
+/// Asks for a particle from the pool.
+pub struct EmitParticleEvent {
+    pub kind: ParticleKind,
+    pub position: Vec3,
+    pub velocity: Vec3,
+}
+
+/// A pooled particle. Hidden particles are free to be emitted again.
+#[derive(Component)]
+pub struct Particle {
+    kind: ParticleKind,
+    velocity: Vec3,
+    age: f32,
+    lifetime: f32,
+}
+
+/// Particles waiting to be emitted. They are spawned once and reused rather than
+/// despawned when they fade.
+#[derive(Resource, Default)]
+pub struct ParticlePool {
+    free: Vec<Entity>,
+    spawned: usize,
+}
+
+impl ParticlePool {
+    /// Particles spawned so far, whether in use or not.
+    pub fn len(&self) -> usize {
+        self.spawned
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spawned == 0
+    }
+
+    /// Particles not currently in use.
+    pub fn free(&self) -> usize {
+        self.free.len()
+    }
+}
+
+#[derive(Resource)]
+struct ParticleAssets {
+    mesh: Handle<Mesh>,
+    wake: Handle<StandardMaterial>,
+    bubble: Handle<StandardMaterial>,
+}
+
+/// Particles are only drawn when rendering is available; headless ponds still pool them.
+fn setup_particle_assets(
+    mut commands: Commands,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    materials: Option<ResMut<Assets<StandardMaterial>>>,
+) {
+    if let (Some(mut meshes), Some(mut materials)) = (meshes, materials) {
+        commands.insert_resource(ParticleAssets {
+            mesh: meshes.add(Mesh::from(shape::Icosphere {
+                radius: 0.5,
+                subdivisions: 1,
+            })),
+            wake: materials.add(StandardMaterial {
+                base_color: Color::rgba(0.8, 0.9, 1.0, 0.35),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            }),
+            bubble: materials.add(StandardMaterial {
+                base_color: Color::rgba(1.0, 1.0, 1.0, 0.6),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            }),
+        });
+    }
+}
+
+fn fill_particle_pool_system(
+    mut commands: Commands,
+    settings: Res<ParticleSettings>,
+    particle_assets: Option<Res<ParticleAssets>>,
+    mut pool: ResMut<ParticlePool>,
+) {
+    while pool.spawned < settings.pool_size {
+        let particle = Particle {
+            kind: ParticleKind::Wake,
+            velocity: Vec3::ZERO,
+            age: 0.0,
+            lifetime: 0.0,
+        };
+        let entity = match &particle_assets {
+            Some(particle_assets) => commands.spawn((
+                PbrBundle {
+                    mesh: particle_assets.mesh.clone(),
+                    material: particle_assets.wake.clone(),
+                    visibility: Visibility::INVISIBLE,
+                    ..default()
+                },
+                particle,
+            )),
+            None => commands.spawn((
+                TransformBundle::default(),
+                VisibilityBundle {
+                    visibility: Visibility::INVISIBLE,
+                    ..default()
+                },
+                particle,
+            )),
+        }
+        .id();
+        pool.free.push(entity);
+        pool.spawned += 1;
+    }
+}
+
+fn wake_system(
+    time: Res<Time>,
+    settings: Res<ParticleSettings>,
+    fishes: Query<(&Transform, &Moveable), With<Fish>>,
+    mut ev_emit: EventWriter<EmitParticleEvent>,
+) {
+    if settings.wake_speed <= 0.0 {
+        return;
+    }
+
+    // Particles are cosmetic and don't touch the seeded simulation RNG.
+    let mut rng = rand::thread_rng();
+    let delta = time.delta_seconds();
+    fishes.for_each(|(transform, moveable)| {
+        let speed = moveable.velocity.length();
+        if speed <= settings.wake_speed {
+            return;
+        }
+
+        let rate = settings.wake_rate * (speed / settings.wake_speed - 1.0);
+        if rng.gen::<f32>() >= rate * delta {
+            return;
+        }
+
+        let heading = moveable.velocity / speed;
+        let kind = if rng.gen::<f32>() < settings.bubble_chance {
+            ParticleKind::Bubble
+        } else {
+            ParticleKind::Wake
+        };
+        let spread: f32 = rng.gen_range(-1.0..=1.0);
+        let side = spread * heading.cross(Vec3::Z);
+        ev_emit.send(EmitParticleEvent {
+            kind,
+            position: transform.translation - heading * transform.scale.x,
+            velocity: 0.2 * speed * (side - heading),
+        });
+    });
+}
+
+fn join_burst_system(
+    settings: Res<ParticleSettings>,
+    mut ev_joined: EventReader<JoinedPlayerEvent>,
+    fishes: Query<&Transform>,
+    mut ev_emit: EventWriter<EmitParticleEvent>,
+) {
+    let mut rng = rand::thread_rng();
+    for JoinedPlayerEvent(fish) in ev_joined.iter() {
+        let position = match fishes.get(*fish) {
+            Ok(transform) => transform.translation,
+            Err(_) => continue,
+        };
+
+        ev_emit.send_batch((0..settings.join_burst).map(|_| {
+            let speed: f32 = rng.gen_range(2.0..6.0);
+            EmitParticleEvent {
+                kind: ParticleKind::Bubble,
+                position,
+                velocity: speed * random_direction(&mut rng),
+            }
+        }));
+    }
+}
+
+fn emit_particle_system(
+    settings: Res<ParticleSettings>,
+    particle_assets: Option<Res<ParticleAssets>>,
+    mut pool: ResMut<ParticlePool>,
+    mut ev_emit: EventReader<EmitParticleEvent>,
+    mut particles: Query<(
+        &mut Particle,
+        &mut Transform,
+        &mut Visibility,
+        Option<&mut Handle<StandardMaterial>>,
+    )>,
+) {
+    for emit in ev_emit.iter() {
+        let entity = match pool.free.pop() {
+            Some(entity) => entity,
+            // Every particle is in use, so this one is dropped.
+            None => continue,
+        };
+        let (mut particle, mut transform, mut visibility, material) =
+            match particles.get_mut(entity) {
+                Ok(particle) => particle,
+                // Spawned by the pool this frame and not in the world yet.
+                Err(_) => {
+                    pool.free.push(entity);
+                    continue;
+                }
+            };
+
+        particle.kind = emit.kind;
+        particle.velocity = emit.velocity;
+        particle.age = 0.0;
+        particle.lifetime = match emit.kind {
+            ParticleKind::Wake => settings.wake_lifetime,
+            ParticleKind::Bubble => settings.bubble_lifetime,
+        };
+        *transform = Transform::from_translation(emit.position).with_scale(Vec3::ZERO);
+        visibility.is_visible = true;
+
+        if let (Some(particle_assets), Some(mut material)) = (&particle_assets, material) {
+            *material = match emit.kind {
+                ParticleKind::Wake => particle_assets.wake.clone(),
+                ParticleKind::Bubble => particle_assets.bubble.clone(),
+            };
+        }
+    }
+}
+
+fn particle_system(
+    time: Res<Time>,
+    settings: Res<ParticleSettings>,
+    mut pool: ResMut<ParticlePool>,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut Visibility)>,
+) {
+    let delta = time.delta_seconds();
+    particles.for_each_mut(|(entity, mut particle, mut transform, mut visibility)| {
+        if !visibility.is_visible {
+            return;
+        }
+
+        particle.age += delta;
+        if particle.age >= particle.lifetime {
+            visibility.is_visible = false;
+            pool.free.push(entity);
+            return;
+        }
+
+        let progress = particle.age / particle.lifetime;
+        let size = match particle.kind {
+            // Wakes spread out and thin; bubbles swell a little as they rise.
+            ParticleKind::Wake => 1.0 + progress,
+            ParticleKind::Bubble => {
+                particle.velocity.z += settings.buoyancy * delta;
+                0.4 + 0.2 * progress
+            }
+        };
+        particle.velocity *= 1.0 - (2.0 * delta).min(1.0);
+        transform.translation += particle.velocity * delta;
+        transform.scale = Vec3::splat(size * (1.0 - progress * progress));
+    });
+}
+
+/// Hides every particle still in flight when the session ends.
+fn recall_particles_system(
+    mut pool: ResMut<ParticlePool>,
+    mut particles: Query<(Entity, &mut Visibility), With<Particle>>,
+) {
+    particles.for_each_mut(|(entity, mut visibility)| {
+        if visibility.is_visible {
+            visibility.is_visible = false;
+            pool.free.push(entity);
+        }
+    });
+}
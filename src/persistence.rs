@@ -0,0 +1,274 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    fishes::{Fish, FishAssets},
+    forces::{ForceWeights, MaxSpeed, Moveable, Wander},
+    genetics::Genome,
+    groups::{next_group_id, set_next_group_id, Groupable},
+    input::ClickToMove,
+    lifecycle::{Age, Growth},
+    random::PondRng,
+    states::{GameState, Session},
+    variety::{KoiSize, KoiVariety},
+};
+
+pub struct PersistencePlugin;
+
+impl Plugin for PersistencePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SaveSettings>()
+            .add_event::<SavePondEvent>()
+            .add_event::<LoadPondEvent>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Running)
+                    .with_system(save_load_input_system)
+                    .with_system(save_pond_system.after(save_load_input_system))
+                    .with_system(load_pond_system.after(save_load_input_system)),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct SaveSettings {
+    pub path: PathBuf,
+}
+
+impl Default for SaveSettings {
+    /// `pond.ron` in the user's data directory, or the working directory if there is none.
+    fn default() -> Self {
+        let dir = dirs::data_dir()
+            .map(|dir| dir.join("homekoi"))
+            .unwrap_or_default();
+        Self {
+            path: dir.join("pond.ron"),
+        }
+    }
+}
+
+pub struct SavePondEvent;
+
+pub struct LoadPondEvent;
+
+/// Everything needed to rebuild a pond: its fish, the group id registry and the RNG seed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PondSnapshot {
+    pub seed: u64,
+    pub next_group_id: usize,
+    pub fishes: Vec<FishSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FishSnapshot {
+    pub player: bool,
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub velocity: [f32; 3],
+    pub group_id: Option<usize>,
+    #[serde(default)]
+    pub weights: ForceWeights,
+    #[serde(default = "default_max_speed")]
+    pub max_speed: f32,
+    /// The fish's current wander force; the player doesn't wander.
+    #[serde(default)]
+    pub wander: Option<[f32; 3]>,
+    #[serde(default)]
+    pub variety: KoiVariety,
+    #[serde(default = "default_size")]
+    pub size: f32,
+    #[serde(default)]
+    pub genome: Genome,
+    /// Older saves have neither, and load every fish as a newborn adult.
+    #[serde(default)]
+    pub age: Age,
+    #[serde(default)]
+    pub growth: Growth,
+}
+
+fn default_size() -> f32 {
+    KoiSize::default().0
+}
+
+fn default_max_speed() -> f32 {
+    MaxSpeed::default().0
+}
+
+impl PondSnapshot {
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, contents)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        ron::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+fn save_load_input_system(
+    keys: Res<Input<KeyCode>>,
+    mut ev_save: EventWriter<SavePondEvent>,
+    mut ev_load: EventWriter<LoadPondEvent>,
+) {
+    if keys.just_pressed(KeyCode::F5) {
+        ev_save.send(SavePondEvent);
+    }
+
+    if keys.just_pressed(KeyCode::F9) {
+        ev_load.send(LoadPondEvent);
+    }
+}
+
+fn save_pond_system(
+    mut ev_save: EventReader<SavePondEvent>,
+    settings: Res<SaveSettings>,
+    mut pond_rng: ResMut<PondRng>,
+    fishes: Query<
+        (
+            &Transform,
+            &Moveable,
+            &Groupable,
+            Option<&Wander>,
+            Option<&ClickToMove>,
+            Option<&KoiVariety>,
+            Option<&KoiSize>,
+            Option<&Genome>,
+            Option<&Age>,
+            Option<&Growth>,
+            Option<&ForceWeights>,
+            Option<&MaxSpeed>,
+        ),
+        With<Fish>,
+    >,
+) {
+    if ev_save.iter().count() == 0 {
+        return;
+    }
+
+    // Reseeding here lets both the running pond and the restored one continue
+    // from the same point in the random sequence.
+    let seed = pond_rng.rng().gen();
+    pond_rng.reseed(seed);
+
+    let snapshot = PondSnapshot {
+        seed,
+        next_group_id: next_group_id(),
+        fishes: fishes
+            .iter()
+            .map(
+                |(
+                    transform,
+                    moveable,
+                    groupable,
+                    wander,
+                    click_to_move,
+                    variety,
+                    size,
+                    genome,
+                    age,
+                    growth,
+                    weights,
+                    max_speed,
+                )| {
+                    FishSnapshot {
+                        player: click_to_move.is_some(),
+                        translation: transform.translation.to_array(),
+                        rotation: transform.rotation.to_array(),
+                        velocity: moveable.velocity.to_array(),
+                        group_id: groupable.id,
+                        weights: weights.copied().unwrap_or_default(),
+                        max_speed: max_speed.copied().unwrap_or_default().0,
+                        wander: wander.map(|wander| wander.force.to_array()),
+                        variety: variety.copied().unwrap_or_default(),
+                        size: size.copied().unwrap_or_default().0,
+                        genome: genome.copied().unwrap_or_default(),
+                        age: age.copied().unwrap_or_default(),
+                        growth: growth.copied().unwrap_or_default(),
+                    }
+                },
+            )
+            .collect(),
+    };
+
+    match snapshot.save(&settings.path) {
+        Ok(()) => info!("Saved pond to {}", settings.path.display()),
+        Err(e) => error!("Failed to save pond to {}: {}", settings.path.display(), e),
+    }
+}
+
+fn load_pond_system(
+    mut commands: Commands,
+    mut ev_load: EventReader<LoadPondEvent>,
+    settings: Res<SaveSettings>,
+    fish_assets: Res<FishAssets>,
+    mut pond_rng: ResMut<PondRng>,
+    fishes: Query<Entity, With<Fish>>,
+) {
+    if ev_load.iter().count() == 0 {
+        return;
+    }
+
+    let snapshot = match PondSnapshot::load(&settings.path) {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            error!(
+                "Failed to load pond from {}: {}",
+                settings.path.display(),
+                e
+            );
+            return;
+        }
+    };
+
+    fishes.for_each(|entity| commands.entity(entity).despawn_recursive());
+
+    for fish in snapshot.fishes.iter() {
+        let transform = Transform {
+            translation: Vec3::from(fish.translation),
+            rotation: Quat::from_array(fish.rotation),
+            ..default()
+        };
+
+        let mut entity = if fish.player {
+            commands.spawn((Fish::new_player(transform, &fish_assets), Session))
+        } else {
+            commands.spawn((Fish::new_npc(transform, &fish_assets), Session))
+        };
+
+        entity.insert((
+            Moveable {
+                velocity: Vec3::from(fish.velocity),
+            },
+            Groupable { id: fish.group_id },
+            fish.variety,
+            KoiSize(fish.size),
+            // Saved alongside the genome so ponds restore without the genetics
+            // plugin; re-expressing a genome gives back the same weights.
+            fish.genome,
+            fish.weights,
+            MaxSpeed(fish.max_speed),
+            fish.age,
+            fish.growth,
+        ));
+        if let Some(force) = fish.wander {
+            entity.insert(Wander {
+                force: Vec3::from(force),
+            });
+        }
+    }
+
+    set_next_group_id(snapshot.next_group_id);
+    pond_rng.reseed(snapshot.seed);
+
+    info!("Loaded pond from {}", settings.path.display());
+}
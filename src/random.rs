@@ -1,8 +1,44 @@
 use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::f32::consts::PI;
 
-pub fn random_direction() -> Vec3 {
-    let angle_ratio: f32 = rand::random();
+/// Seeded source of randomness shared by the simulation so a pond can be replayed.
+#[derive(Resource)]
+pub struct PondRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl Default for PondRng {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
+
+impl PondRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restarts the sequence from `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+}
+
+pub fn random_direction(rng: &mut impl Rng) -> Vec3 {
+    let angle_ratio: f32 = rng.gen();
     let radians = 2.0 * PI * angle_ratio;
 
     Vec3::new(radians.cos(), radians.sin(), 0.0)
@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+
+use crate::{
+    groups::JoinedPlayerEvent, levels::LevelCompleteEvent, lifecycle::FishBornEvent,
+    random::PondRng,
+};
+
+/// Registers the types shared by every state, and the events and resources that
+/// several plugins share, so any of those plugins can be disabled without the
+/// others losing them. The [`GameState`] state itself is added by the app, which
+/// picks the state it starts in.
+pub struct StatesPlugin;
+
+impl Plugin for StatesPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Session>()
+            .init_resource::<PondRng>()
+            .add_event::<JoinedPlayerEvent>()
+            .add_event::<FishBornEvent>()
+            .add_event::<LevelCompleteEvent>();
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub enum GameState {
+    AssetLoading,
+    MainMenu,
+    Running,
+    Paused,
+    Results,
+}
+
+/// Marks entities that belong to a single play session and are despawned when it ends.
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Session;
+
+pub fn despawn_session_system(mut commands: Commands, entities: Query<Entity, With<Session>>) {
+    entities.for_each(|entity| commands.entity(entity).despawn_recursive());
+}
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{fishes::Fish, lifecycle::Growth, random::PondRng, states::GameState};
+
+pub struct VarietyPlugin;
+
+impl Plugin for VarietyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VarietySettings>()
+            .init_resource::<VarietyMaterials>()
+            .register_type::<KoiVariety>()
+            .register_type::<KoiSize>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Running)
+                    .with_system(assign_variety_system)
+                    .with_system(koi_size_system.after(assign_variety_system))
+                    .with_system(koi_material_system),
+            );
+    }
+}
+
+/// The colour pattern of a koi.
+#[derive(
+    Component, Reflect, Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash,
+)]
+#[reflect(Component)]
+pub enum KoiVariety {
+    /// White with red markings.
+    #[default]
+    Kohaku,
+    /// White with red and black markings.
+    Sanke,
+    /// Black with red and white markings.
+    Showa,
+    /// Solid metallic gold.
+    Ogon,
+    /// Blue-grey back with an orange belly.
+    Asagi,
+    /// Black with white markings.
+    ShiroUtsuri,
+    /// Solid brown.
+    Chagoi,
+}
+
+impl KoiVariety {
+    pub const ALL: [KoiVariety; 7] = [
+        KoiVariety::Kohaku,
+        KoiVariety::Sanke,
+        KoiVariety::Showa,
+        KoiVariety::Ogon,
+        KoiVariety::Asagi,
+        KoiVariety::ShiroUtsuri,
+        KoiVariety::Chagoi,
+    ];
+
+    /// Colours applied, in turn, to the materials of the fish model.
+    pub fn palette(&self) -> &'static [Color] {
+        const WHITE: Color = Color::rgb(0.95, 0.93, 0.9);
+        const RED: Color = Color::rgb(0.85, 0.15, 0.05);
+        const BLACK: Color = Color::rgb(0.05, 0.05, 0.06);
+        const GOLD: Color = Color::rgb(0.95, 0.7, 0.2);
+        const BLUE_GREY: Color = Color::rgb(0.4, 0.5, 0.6);
+        const ORANGE: Color = Color::rgb(0.95, 0.45, 0.1);
+        const BROWN: Color = Color::rgb(0.45, 0.3, 0.15);
+
+        match self {
+            KoiVariety::Kohaku => &[WHITE, RED],
+            KoiVariety::Sanke => &[WHITE, RED, BLACK],
+            KoiVariety::Showa => &[BLACK, RED, WHITE],
+            KoiVariety::Ogon => &[GOLD],
+            KoiVariety::Asagi => &[BLUE_GREY, ORANGE],
+            KoiVariety::ShiroUtsuri => &[BLACK, WHITE],
+            KoiVariety::Chagoi => &[BROWN],
+        }
+    }
+
+    /// The variety of a juvenile bred from `a` and `b`.
+    ///
+    /// Juveniles take after either parent or any variety whose pattern carries
+    /// both parents' colours, e.g. Kohaku and Shiro Utsuri can produce Sanke.
+    pub fn cross(a: KoiVariety, b: KoiVariety, rng: &mut impl Rng) -> KoiVariety {
+        if a == b {
+            return a;
+        }
+
+        let carries_both = |variety: &KoiVariety| {
+            let palette = variety.palette();
+            a.palette()
+                .iter()
+                .chain(b.palette())
+                .all(|color| palette.contains(color))
+        };
+        let candidates: Vec<KoiVariety> = [a, b]
+            .into_iter()
+            .chain(
+                KoiVariety::ALL
+                    .into_iter()
+                    .filter(|variety| *variety != a && *variety != b && carries_both(variety)),
+            )
+            .collect();
+
+        candidates[rng.gen_range(0..candidates.len())]
+    }
+
+    pub fn is_metallic(&self) -> bool {
+        matches!(self, KoiVariety::Ogon)
+    }
+}
+
+/// Uniform scale of a fish's model and proximity collider.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct KoiSize(pub f32);
+
+impl Default for KoiSize {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// How varieties and sizes are drawn for newly spawned fish.
+#[derive(Resource, Debug, Clone)]
+pub struct VarietySettings {
+    /// Relative likelihood of each variety. Varieties not listed never spawn.
+    pub distribution: Vec<(KoiVariety, f32)>,
+    pub min_size: f32,
+    pub max_size: f32,
+}
+
+impl Default for VarietySettings {
+    fn default() -> Self {
+        Self {
+            distribution: vec![
+                (KoiVariety::Kohaku, 3.0),
+                (KoiVariety::Sanke, 2.0),
+                (KoiVariety::Showa, 2.0),
+                (KoiVariety::Ogon, 1.0),
+                (KoiVariety::Asagi, 1.0),
+                (KoiVariety::ShiroUtsuri, 1.0),
+                (KoiVariety::Chagoi, 0.5),
+            ],
+            min_size: 0.8,
+            max_size: 1.25,
+        }
+    }
+}
+
+impl VarietySettings {
+    /// Draws a variety and size. An empty or all-zero distribution always yields the default variety.
+    pub fn choose(&self, rng: &mut impl Rng) -> (KoiVariety, KoiSize) {
+        let variety = match WeightedIndex::new(self.distribution.iter().map(|(_, weight)| *weight))
+        {
+            Ok(index) => self.distribution[index.sample(rng)].0,
+            Err(_) => KoiVariety::default(),
+        };
+        let size = self.min_size + (self.max_size - self.min_size) * rng.gen::<f32>();
+
+        (variety, KoiSize(size))
+    }
+}
+
+/// Per-variety copies of the fish model's materials.
+#[derive(Resource, Default)]
+struct VarietyMaterials {
+    by_key: HashMap<(KoiVariety, usize, Handle<StandardMaterial>), Handle<StandardMaterial>>,
+}
+
+impl VarietyMaterials {
+    /// The `index`th material of `variety`, derived from the model's `original` material.
+    fn get_or_add(
+        &mut self,
+        variety: KoiVariety,
+        index: usize,
+        original: Handle<StandardMaterial>,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Handle<StandardMaterial> {
+        self.by_key
+            .entry((variety, index, original.clone()))
+            .or_insert_with(|| {
+                let palette = variety.palette();
+                let mut material = materials.get(&original).cloned().unwrap_or_default();
+                material.base_color = palette[index % palette.len()];
+                if variety.is_metallic() {
+                    material.metallic = 0.8;
+                    material.perceptual_roughness = 0.3;
+                }
+                materials.add(material)
+            })
+            .clone()
+    }
+}
+
+/// Marks fish whose model materials have been replaced with their variety's.
+#[derive(Component)]
+struct VarietyApplied;
+
+/// The model's own material on a recoloured mesh, kept so it can be recoloured again.
+#[derive(Component)]
+struct OriginalMaterial(Handle<StandardMaterial>);
+
+fn assign_variety_system(
+    mut commands: Commands,
+    settings: Res<VarietySettings>,
+    mut pond_rng: ResMut<PondRng>,
+    fishes: Query<Entity, (With<Fish>, Without<KoiVariety>)>,
+) {
+    let rng = pond_rng.rng();
+    fishes.for_each(|entity| {
+        let (variety, size) = settings.choose(rng);
+        commands.entity(entity).insert((variety, size));
+    });
+}
+
+/// Scales fish to their size; the proximity collider follows the transform's scale.
+fn koi_size_system(
+    mut fishes: Query<
+        (&KoiSize, Option<&Growth>, &mut Transform),
+        Or<(Changed<KoiSize>, Changed<Growth>)>,
+    >,
+) {
+    fishes.for_each_mut(|(size, growth, mut transform)| {
+        transform.scale = Vec3::splat(size.0 * growth.map_or(1.0, Growth::scale));
+    });
+}
+
+fn koi_material_system(
+    mut commands: Commands,
+    mut variety_materials: ResMut<VarietyMaterials>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    fishes: Query<(Entity, &KoiVariety, Option<&VarietyApplied>), With<Fish>>,
+    changed: Query<(), Changed<KoiVariety>>,
+    children: Query<&Children>,
+    mut meshes: Query<(&mut Handle<StandardMaterial>, Option<&OriginalMaterial>)>,
+) {
+    fishes.for_each(|(entity, variety, applied)| {
+        if applied.is_some() && !changed.contains(entity) {
+            return;
+        }
+
+        // The scene is instanced a few frames after the fish spawns, so keep
+        // trying until its meshes show up.
+        let mut index = 0;
+        for child in children.iter_descendants(entity) {
+            if let Ok((mut handle, original)) = meshes.get_mut(child) {
+                let original = match original {
+                    Some(original) => original.0.clone(),
+                    None => {
+                        commands
+                            .entity(child)
+                            .insert(OriginalMaterial(handle.clone()));
+                        handle.clone()
+                    }
+                };
+
+                *handle = variety_materials.get_or_add(*variety, index, original, &mut materials);
+                index += 1;
+            }
+        }
+
+        if index > 0 && applied.is_none() {
+            commands.entity(entity).insert(VarietyApplied);
+        }
+    });
+}
@@ -0,0 +1,49 @@
+use homekoi::audio::MusicLayer;
+
+fn layer(fade_in_from: usize, full_at: usize) -> MusicLayer {
+    MusicLayer {
+        path: "sounds/background.ogg".to_string(),
+        volume: 0.8,
+        fade_in_from,
+        full_at,
+    }
+}
+
+#[test]
+fn layer_is_silent_below_its_range() {
+    let layer = layer(5, 20);
+
+    assert_eq!(layer.volume_for(0), 0.0);
+    assert_eq!(layer.volume_for(5), 0.0);
+}
+
+#[test]
+fn layer_fades_in_across_its_range() {
+    let layer = layer(5, 20);
+
+    assert!((layer.volume_for(10) - 0.8 / 3.0).abs() < 1e-6);
+    assert!(layer.volume_for(15) > layer.volume_for(10));
+    assert!(layer.volume_for(19) < 0.8);
+}
+
+#[test]
+fn layer_is_full_above_its_range() {
+    let layer = layer(5, 20);
+
+    assert_eq!(layer.volume_for(20), 0.8);
+    assert_eq!(layer.volume_for(100), 0.8);
+}
+
+#[test]
+fn layer_without_a_fade_switches_on_at_once() {
+    let layer = layer(5, 5);
+
+    assert_eq!(layer.volume_for(4), 0.0);
+    assert_eq!(layer.volume_for(5), 0.8);
+    assert_eq!(layer.volume_for(6), 0.8);
+}
+
+#[test]
+fn layer_from_zero_plays_from_the_start() {
+    assert_eq!(layer(0, 0).volume_for(0), 0.8);
+}
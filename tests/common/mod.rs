@@ -0,0 +1,88 @@
+#![allow(dead_code)]
+
+use bevy::prelude::*;
+use homekoi::{
+    fishes::Fish,
+    forces::{Moveable, Wander},
+    groups::Groupable,
+    headless::HeadlessSimPlugin,
+};
+
+pub const TIMESTEP: f32 = 1.0 / 60.0;
+
+/// A headless pond that advances in fixed ticks.
+pub struct SimHarness {
+    pub app: App,
+}
+
+impl SimHarness {
+    pub fn new(seed: u64) -> Self {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(HeadlessSimPlugin {
+                timestep: Some(TIMESTEP),
+                seed: Some(seed),
+            });
+
+        Self { app }
+    }
+
+    pub fn spawn_npc(&mut self, position: Vec3) -> Entity {
+        self.app
+            .world
+            .spawn(Fish::new_headless_npc(Transform::from_translation(
+                position,
+            )))
+            .id()
+    }
+
+    pub fn spawn_player(&mut self, position: Vec3) -> Entity {
+        self.app
+            .world
+            .spawn(Fish::new_headless_player(Transform::from_translation(
+                position,
+            )))
+            .id()
+    }
+
+    /// An NPC that never wanders, so only the flocking forces move it.
+    pub fn spawn_calm_npc(&mut self, position: Vec3) -> Entity {
+        let entity = self.spawn_npc(position);
+        self.app.world.entity_mut(entity).remove::<Wander>();
+        entity
+    }
+
+    pub fn set_group(&mut self, entity: Entity, id: Option<usize>) {
+        self.app.world.get_mut::<Groupable>(entity).unwrap().id = id;
+    }
+
+    pub fn set_velocity(&mut self, entity: Entity, velocity: Vec3) {
+        self.app.world.get_mut::<Moveable>(entity).unwrap().velocity = velocity;
+    }
+
+    pub fn step(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.app.update();
+        }
+    }
+
+    pub fn position(&self, entity: Entity) -> Vec3 {
+        self.app.world.get::<Transform>(entity).unwrap().translation
+    }
+
+    pub fn velocity(&self, entity: Entity) -> Vec3 {
+        self.app.world.get::<Moveable>(entity).unwrap().velocity
+    }
+
+    pub fn group(&self, entity: Entity) -> Option<usize> {
+        self.app.world.get::<Groupable>(entity).unwrap().id
+    }
+
+    /// Mean distance of `entities` from their center of mass.
+    pub fn spread(&self, entities: &[Entity]) -> f32 {
+        let positions: Vec<Vec3> = entities.iter().map(|e| self.position(*e)).collect();
+        let center = positions.iter().copied().sum::<Vec3>() / positions.len() as f32;
+
+        positions.iter().map(|p| p.distance(center)).sum::<f32>() / positions.len() as f32
+    }
+}
@@ -0,0 +1,82 @@
+use homekoi::{
+    genetics::{Genome, MutationSettings},
+    random::PondRng,
+};
+
+fn varied_genome() -> Genome {
+    Genome {
+        cohesion: 0.5,
+        separation: 1.5,
+        alignment: 2.0,
+        wander: 0.25,
+        max_speed: 18.0,
+    }
+}
+
+#[test]
+fn mutation_is_deterministic_for_a_seed() {
+    let settings = MutationSettings::default();
+    let genome = varied_genome();
+
+    let a = genome.mutate(PondRng::new(11).rng(), &settings);
+    let b = genome.mutate(PondRng::new(11).rng(), &settings);
+
+    assert_eq!(a, b);
+}
+
+#[test]
+fn mutation_without_rate_keeps_genes() {
+    let settings = MutationSettings {
+        rate: 0.0,
+        ..Default::default()
+    };
+    let genome = varied_genome();
+
+    assert_eq!(genome.mutate(PondRng::new(11).rng(), &settings), genome);
+}
+
+#[test]
+fn mutation_stays_within_bounds() {
+    let settings = MutationSettings {
+        rate: 1.0,
+        strength: 10.0,
+        ..Default::default()
+    };
+    let mut pond_rng = PondRng::new(11);
+    let mut genome = varied_genome();
+
+    for _ in 0..1000 {
+        genome = genome.mutate(pond_rng.rng(), &settings);
+        for weight in [
+            genome.cohesion,
+            genome.separation,
+            genome.alignment,
+            genome.wander,
+        ] {
+            assert!((settings.weight_range.0..=settings.weight_range.1).contains(&weight));
+        }
+        assert!(
+            (settings.max_speed_range.0..=settings.max_speed_range.1).contains(&genome.max_speed)
+        );
+    }
+}
+
+#[test]
+fn inherited_genes_come_from_a_parent() {
+    let settings = MutationSettings {
+        rate: 0.0,
+        ..Default::default()
+    };
+    let a = varied_genome();
+    let b = Genome::default();
+    let mut pond_rng = PondRng::new(11);
+
+    for _ in 0..100 {
+        let child = Genome::inherit(&a, &b, pond_rng.rng(), &settings);
+        assert!(child.cohesion == a.cohesion || child.cohesion == b.cohesion);
+        assert!(child.separation == a.separation || child.separation == b.separation);
+        assert!(child.alignment == a.alignment || child.alignment == b.alignment);
+        assert!(child.wander == a.wander || child.wander == b.wander);
+        assert!(child.max_speed == a.max_speed || child.max_speed == b.max_speed);
+    }
+}
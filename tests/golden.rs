@@ -0,0 +1,83 @@
+//! Golden-trajectory snapshots of small, seeded ponds.
+//!
+//! Snapshots live in `tests/golden` and are committed. Run with `UPDATE_GOLDEN=1`
+//! to record new snapshots or rewrite them after an intended behaviour change; a
+//! snapshot that hasn't been recorded yet is skipped with a note.
+
+mod common;
+
+use std::{env, fs, path::PathBuf};
+
+use bevy::prelude::*;
+use common::SimHarness;
+
+const TOLERANCE: f32 = 1e-3;
+
+fn check_golden(name: &str, positions: &[Vec3]) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.ron", name));
+    let actual: Vec<[f32; 3]> = positions.iter().map(|p| p.to_array()).collect();
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        let contents =
+            ron::ser::to_string_pretty(&actual, ron::ser::PrettyConfig::default()).unwrap();
+        fs::write(&path, contents).unwrap();
+        return;
+    }
+
+    if !path.exists() {
+        eprintln!(
+            "{}: no snapshot at {}, skipping; run with UPDATE_GOLDEN=1 to record it",
+            name,
+            path.display()
+        );
+        return;
+    }
+
+    let expected: Vec<[f32; 3]> = ron::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(expected.len(), actual.len(), "{}: fish count changed", name);
+
+    for (i, (expected, actual)) in expected.iter().zip(actual.iter()).enumerate() {
+        let distance = Vec3::from(*expected).distance(Vec3::from(*actual));
+        assert!(
+            distance < TOLERANCE,
+            "{}: fish {} is at {:?}, expected {:?}",
+            name,
+            i,
+            actual,
+            expected
+        );
+    }
+}
+
+#[test]
+fn golden_school_around_player() {
+    let mut sim = SimHarness::new(42);
+    let mut fishes = vec![sim.spawn_player(Vec3::ZERO)];
+    for x in -2..=2 {
+        for y in -2..=2 {
+            if x != 0 || y != 0 {
+                fishes.push(sim.spawn_npc(Vec3::new(6.0 * x as f32, 6.0 * y as f32, 0.0)));
+            }
+        }
+    }
+
+    sim.step(300);
+
+    let positions: Vec<Vec3> = fishes.iter().map(|e| sim.position(*e)).collect();
+    check_golden("school_around_player", &positions);
+}
+
+#[test]
+fn golden_wandering_strangers() {
+    let mut sim = SimHarness::new(42);
+    let fishes: Vec<Entity> = (0..10)
+        .map(|i| sim.spawn_npc(Vec3::new(50.0 * i as f32, 0.0, 0.0)))
+        .collect();
+
+    sim.step(300);
+
+    let positions: Vec<Vec3> = fishes.iter().map(|e| sim.position(*e)).collect();
+    check_golden("wandering_strangers", &positions);
+}
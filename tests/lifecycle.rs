@@ -0,0 +1,115 @@
+mod common;
+
+use bevy::prelude::*;
+use common::SimHarness;
+use homekoi::{
+    fishes::Fish,
+    groups::Groupable,
+    lifecycle::{Food, Growth, LifecyclePlugin, LifecycleSettings},
+};
+
+fn lifecycle_sim(settings: LifecycleSettings) -> SimHarness {
+    let mut sim = SimHarness::new(3);
+    sim.app
+        .insert_resource(settings)
+        .add_plugin(LifecyclePlugin);
+    sim
+}
+
+fn no_food() -> LifecycleSettings {
+    LifecycleSettings {
+        food_interval: f32::INFINITY,
+        ..default()
+    }
+}
+
+#[test]
+fn adults_of_the_same_group_breed() {
+    let mut sim = lifecycle_sim(LifecycleSettings {
+        min_breeding_age: 0.0,
+        courtship_time: 0.25,
+        ..no_food()
+    });
+    let a = sim.spawn_calm_npc(Vec3::new(-1.0, 0.0, 0.0));
+    let b = sim.spawn_calm_npc(Vec3::new(1.0, 0.0, 0.0));
+    sim.set_group(a, Some(7));
+    sim.set_group(b, Some(7));
+
+    sim.step(60);
+
+    let mut fishes = sim
+        .app
+        .world
+        .query_filtered::<(&Groupable, &Growth), With<Fish>>();
+    let juveniles: Vec<_> = fishes
+        .iter(&sim.app.world)
+        .filter(|(_, growth)| !growth.is_adult())
+        .map(|(groupable, _)| groupable.id)
+        .collect();
+    assert_eq!(juveniles, vec![Some(7)]);
+}
+
+#[test]
+fn distant_fish_do_not_breed() {
+    let mut sim = lifecycle_sim(LifecycleSettings {
+        min_breeding_age: 0.0,
+        courtship_time: 0.25,
+        ..no_food()
+    });
+    sim.spawn_calm_npc(Vec3::new(-20.0, 0.0, 0.0));
+    sim.spawn_calm_npc(Vec3::new(20.0, 0.0, 0.0));
+
+    sim.step(60);
+
+    let mut fishes = sim.app.world.query_filtered::<(), With<Fish>>();
+    assert_eq!(fishes.iter(&sim.app.world).count(), 2);
+}
+
+#[test]
+fn juveniles_grow_by_eating() {
+    let settings = no_food();
+    let growth_per_food = settings.growth_per_food;
+    let mut sim = lifecycle_sim(settings);
+    let fish = sim.spawn_calm_npc(Vec3::ZERO);
+    sim.app.world.entity_mut(fish).insert(Growth::juvenile());
+    sim.app
+        .world
+        .spawn((TransformBundle::from_transform(Transform::default()), Food));
+
+    sim.step(2);
+
+    let growth = sim.app.world.get::<Growth>(fish).unwrap();
+    assert_eq!(growth.progress, growth_per_food);
+    let mut food = sim.app.world.query_filtered::<(), With<Food>>();
+    assert_eq!(food.iter(&sim.app.world).count(), 0);
+}
+
+#[test]
+fn adults_leave_food_for_juveniles() {
+    let mut sim = lifecycle_sim(no_food());
+    let adult = sim.spawn_calm_npc(Vec3::ZERO);
+    let juvenile = sim.spawn_calm_npc(Vec3::new(0.0, 20.0, 0.0));
+    sim.app
+        .world
+        .entity_mut(juvenile)
+        .insert(Growth::juvenile());
+    sim.app
+        .world
+        .spawn((TransformBundle::from_transform(Transform::default()), Food));
+
+    sim.step(2);
+
+    assert!(sim.app.world.get::<Growth>(adult).unwrap().is_adult());
+    let mut food = sim.app.world.query_filtered::<(), With<Food>>();
+    assert_eq!(food.iter(&sim.app.world).count(), 1);
+
+    sim.app
+        .world
+        .get_mut::<Transform>(juvenile)
+        .unwrap()
+        .translation = Vec3::ZERO;
+    sim.step(2);
+
+    assert_eq!(food.iter(&sim.app.world).count(), 0);
+    assert!(!sim.app.world.get::<Growth>(juvenile).unwrap().is_adult());
+}
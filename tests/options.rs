@@ -0,0 +1,48 @@
+use std::{env, fs, process};
+
+use homekoi::options::LaunchOptions;
+
+#[test]
+fn command_line_options_override_the_config_file() {
+    let command_line = LaunchOptions {
+        fish_count: Some(10),
+        mute: Some(false),
+        ..Default::default()
+    };
+    let config = LaunchOptions {
+        fish_count: Some(500),
+        seed: Some(3),
+        mute: Some(true),
+        ..Default::default()
+    };
+
+    let options = command_line.or(config);
+
+    assert_eq!(options.fish_count, Some(10));
+    assert_eq!(options.mute, Some(false));
+    assert_eq!(options.seed, Some(3));
+    assert_eq!(options.level, None);
+}
+
+#[test]
+fn config_file_options_can_leave_out_some() {
+    let path = env::temp_dir().join(format!("homekoi-options-{}.ron", process::id()));
+    fs::write(
+        &path,
+        r#"(fish_count: 500, level: "levels/school_run.level.ron", mute: true)"#,
+    )
+    .unwrap();
+
+    let options = LaunchOptions::from_file(&path);
+    fs::remove_file(&path).unwrap();
+    let options = options.unwrap();
+
+    assert_eq!(options.fish_count, Some(500));
+    assert_eq!(
+        options.level.as_deref(),
+        Some("levels/school_run.level.ron")
+    );
+    assert_eq!(options.mute, Some(true));
+    assert_eq!(options.fullscreen, None);
+    assert_eq!(options.config, None);
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use bevy::prelude::*;
+use common::SimHarness;
+use homekoi::particles::{Particle, ParticlePlugin, ParticlePool, ParticleSettings};
+
+fn particle_count(sim: &mut SimHarness) -> usize {
+    sim.app
+        .world
+        .query_filtered::<(), With<Particle>>()
+        .iter(&sim.app.world)
+        .count()
+}
+
+fn visible_particles(sim: &mut SimHarness) -> usize {
+    sim.app
+        .world
+        .query_filtered::<&Visibility, With<Particle>>()
+        .iter(&sim.app.world)
+        .filter(|visibility| visibility.is_visible)
+        .count()
+}
+
+#[test]
+fn joining_the_player_reuses_pooled_particles() {
+    let mut sim = SimHarness::new(1);
+    sim.app
+        .insert_resource(ParticleSettings {
+            pool_size: 8,
+            join_burst: 16,
+            wake_speed: f32::INFINITY,
+            ..default()
+        })
+        .add_plugin(ParticlePlugin);
+    sim.step(1);
+    assert_eq!(particle_count(&mut sim), 8);
+
+    sim.spawn_player(Vec3::ZERO);
+    let npc = sim.spawn_calm_npc(Vec3::new(0.0, 4.0, 0.0));
+    sim.step(10);
+
+    assert_eq!(sim.group(npc), Some(0));
+    assert_eq!(visible_particles(&mut sim), 8);
+    assert_eq!(particle_count(&mut sim), 8);
+
+    sim.step(180);
+
+    assert_eq!(visible_particles(&mut sim), 0);
+    assert_eq!(particle_count(&mut sim), 8);
+    assert_eq!(sim.app.world.resource::<ParticlePool>().free(), 8);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use std::{env, fs, path::PathBuf, process};
+
+use bevy::prelude::*;
+use common::SimHarness;
+use homekoi::{
+    fishes::{Fish, FishAssets},
+    forces::{ForceWeights, MaxSpeed},
+    persistence::{LoadPondEvent, PersistencePlugin, SavePondEvent, SaveSettings},
+};
+
+/// A pond that saves to `path`, without the genetics plugin re-expressing weights.
+fn persistence_sim(path: PathBuf) -> SimHarness {
+    let mut sim = SimHarness::new(5);
+    sim.app
+        .init_resource::<Input<KeyCode>>()
+        .insert_resource(FishAssets {
+            fish_scene: Handle::default(),
+            fish_animation: Handle::default(),
+        })
+        .insert_resource(SaveSettings { path })
+        .add_plugin(PersistencePlugin);
+    sim
+}
+
+#[test]
+fn force_weights_survive_a_round_trip() {
+    let path = env::temp_dir().join(format!("homekoi-weights-{}.ron", process::id()));
+    let mut sim = persistence_sim(path.clone());
+    let weights = ForceWeights {
+        cohesion: 2.0,
+        separation: 0.5,
+        alignment: 1.5,
+        friction: 0.8,
+        wander: 0.25,
+    };
+    let npc = sim.spawn_calm_npc(Vec3::ZERO);
+    sim.app
+        .world
+        .entity_mut(npc)
+        .insert((weights, MaxSpeed(7.0)));
+    sim.step(1);
+
+    sim.app.world.send_event(SavePondEvent);
+    sim.step(1);
+    sim.app.world.send_event(LoadPondEvent);
+    sim.step(2);
+    fs::remove_file(&path).unwrap();
+
+    let mut fishes = sim
+        .app
+        .world
+        .query_filtered::<(&ForceWeights, &MaxSpeed), With<Fish>>();
+    let restored: Vec<_> = fishes
+        .iter(&sim.app.world)
+        .map(|(weights, max_speed)| (*weights, *max_speed))
+        .collect();
+    assert_eq!(restored, vec![(weights, MaxSpeed(7.0))]);
+}
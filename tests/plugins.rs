@@ -0,0 +1,117 @@
+use bevy::{
+    gilrs::GilrsPlugin, log::LogPlugin, prelude::*, render::settings::WgpuSettings,
+    winit::WinitPlugin,
+};
+use bevy_rapier3d::prelude::{NoUserData, RapierPhysicsPlugin};
+use homekoi::{
+    animation::FishAnimationPlugin,
+    audio::{AudioAssets, AudioCuePlugin},
+    camera::CameraPlugin,
+    environment::PondEnvironmentPlugin,
+    fishes::{Fish, FishAssets, FishPlugin},
+    forces::ForcesPlugin,
+    genetics::GeneticsPlugin,
+    group_rings::GroupRingsPlugin,
+    groups::GroupsPlugin,
+    hud::{HudPlugin, HudStats, UiAssets},
+    input::InputPlugin,
+    levels::{LevelAssets, LevelPlugin},
+    lifecycle::LifecyclePlugin,
+    menus::MenusPlugin,
+    minimap::MinimapPlugin,
+    particles::ParticlePlugin,
+    persistence::PersistencePlugin,
+    states::GameState,
+    variety::VarietyPlugin,
+    HomeKoiPlugins,
+};
+
+/// A running pond with every default plugin except the window and the renderer,
+/// and with placeholder assets instead of a loading state.
+fn pond(plugins: impl PluginGroup) -> App {
+    let mut app = App::new();
+    app.insert_resource(WgpuSettings {
+        backends: None,
+        ..default()
+    })
+    .add_plugins(
+        DefaultPlugins
+            .build()
+            .disable::<WinitPlugin>()
+            .disable::<LogPlugin>()
+            .disable::<GilrsPlugin>(),
+    )
+    .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+    .add_plugins(plugins)
+    .insert_resource(FishAssets {
+        fish_scene: Handle::default(),
+        fish_animation: Handle::default(),
+    })
+    .insert_resource(UiAssets {
+        font: Handle::default(),
+    })
+    .insert_resource(AudioAssets {
+        background_music: Handle::default(),
+        bubbles_sfx: Handle::default(),
+    })
+    .insert_resource(LevelAssets {
+        level: Handle::default(),
+    })
+    .add_state(GameState::Running);
+
+    app
+}
+
+/// A player and an NPC that meet, stepped long enough for them to group up.
+fn run_pond(plugins: impl PluginGroup) -> App {
+    let mut app = pond(plugins);
+    app.world
+        .spawn(Fish::new_headless_player(Transform::default()));
+    app.world
+        .spawn(Fish::new_headless_npc(Transform::from_xyz(0.0, 4.0, 0.0)));
+
+    for _ in 0..10 {
+        app.update();
+    }
+
+    app
+}
+
+#[test]
+fn runs_with_the_hud_disabled() {
+    let app = run_pond(HomeKoiPlugins.build().disable::<HudPlugin>());
+
+    assert!(app.world.get_resource::<HudStats>().is_none());
+}
+
+/// One test per member of [`HomeKoiPlugins`] that runs the pond without it.
+macro_rules! runs_without {
+    ($($test:ident: $plugin:ty,)*) => {
+        $(
+            #[test]
+            fn $test() {
+                run_pond(HomeKoiPlugins.build().disable::<$plugin>());
+            }
+        )*
+    };
+}
+
+runs_without! {
+    runs_without_forces: ForcesPlugin,
+    runs_without_groups: GroupsPlugin,
+    runs_without_fish: FishPlugin,
+    runs_without_fish_animation: FishAnimationPlugin,
+    runs_without_variety: VarietyPlugin,
+    runs_without_lifecycle: LifecyclePlugin,
+    runs_without_genetics: GeneticsPlugin,
+    runs_without_camera: CameraPlugin,
+    runs_without_input: InputPlugin,
+    runs_without_audio_cues: AudioCuePlugin,
+    runs_without_levels: LevelPlugin,
+    runs_without_environment: PondEnvironmentPlugin,
+    runs_without_particles: ParticlePlugin,
+    runs_without_minimap: MinimapPlugin,
+    runs_without_group_rings: GroupRingsPlugin,
+    runs_without_menus: MenusPlugin,
+    runs_without_persistence: PersistencePlugin,
+}
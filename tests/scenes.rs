@@ -0,0 +1,43 @@
+mod common;
+
+use bevy::{ecs::entity::EntityMap, prelude::*, scene::serde::SceneDeserializer};
+use common::SimHarness;
+use homekoi::fishes::Fish;
+use serde::de::DeserializeSeed;
+
+#[test]
+fn fish_from_a_scene_file_join_a_group() {
+    let mut sim = SimHarness::new(9);
+    sim.app.register_type::<Fish>();
+    let registry = sim.app.world.resource::<AppTypeRegistry>().clone();
+
+    let mut scene_world = World::new();
+    scene_world.spawn(Fish::new_headless_player(Transform::default()));
+    scene_world.spawn(Fish::new_headless_npc(Transform::from_xyz(4.0, 0.0, 0.0)));
+    let written = DynamicScene::from_world(&scene_world, &registry)
+        .serialize_ron(&registry)
+        .unwrap();
+
+    let mut deserializer = ron::de::Deserializer::from_str(&written).unwrap();
+    let scene = SceneDeserializer {
+        type_registry: &registry.read(),
+    }
+    .deserialize(&mut deserializer)
+    .unwrap();
+    scene
+        .write_to_world(&mut sim.app.world, &mut EntityMap::default())
+        .unwrap();
+
+    sim.step(10);
+
+    let fishes: Vec<Entity> = sim
+        .app
+        .world
+        .query_filtered::<Entity, With<Fish>>()
+        .iter(&sim.app.world)
+        .collect();
+    assert_eq!(fishes.len(), 2);
+    for fish in fishes {
+        assert_eq!(sim.group(fish), Some(0));
+    }
+}
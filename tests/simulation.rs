@@ -0,0 +1,152 @@
+mod common;
+
+use std::{env, process::Command};
+
+use bevy::{
+    prelude::*,
+    tasks::{ComputeTaskPool, TaskPoolBuilder},
+};
+use common::SimHarness;
+
+const THREADS_VAR: &str = "HOMEKOI_TEST_THREADS";
+const POSITIONS_PREFIX: &str = "positions: ";
+
+#[test]
+fn nearby_fish_form_one_group() {
+    let mut sim = SimHarness::new(1);
+    let a = sim.spawn_calm_npc(Vec3::ZERO);
+    let b = sim.spawn_calm_npc(Vec3::new(4.0, 0.0, 0.0));
+
+    sim.step(10);
+
+    assert!(sim.group(a).is_some());
+    assert_eq!(sim.group(a), sim.group(b));
+}
+
+#[test]
+fn distant_fish_stay_ungrouped() {
+    let mut sim = SimHarness::new(1);
+    let a = sim.spawn_calm_npc(Vec3::ZERO);
+    let b = sim.spawn_calm_npc(Vec3::new(100.0, 0.0, 0.0));
+
+    sim.step(10);
+
+    assert_eq!(sim.group(a), None);
+    assert_eq!(sim.group(b), None);
+}
+
+#[test]
+fn fish_near_player_joins_player_group() {
+    let mut sim = SimHarness::new(1);
+    sim.spawn_player(Vec3::ZERO);
+    let npc = sim.spawn_calm_npc(Vec3::new(0.0, 4.0, 0.0));
+
+    sim.step(10);
+
+    assert_eq!(sim.group(npc), Some(0));
+}
+
+#[test]
+fn cohesion_reduces_group_spread() {
+    let mut sim = SimHarness::new(1);
+    let fishes: Vec<Entity> = (0..6)
+        .map(|i| {
+            let angle = i as f32 * std::f32::consts::TAU / 6.0;
+            let fish = sim.spawn_calm_npc(30.0 * Vec3::new(angle.cos(), angle.sin(), 0.0));
+            sim.set_group(fish, Some(1_000));
+            fish
+        })
+        .collect();
+
+    let initial_spread = sim.spread(&fishes);
+    sim.step(120);
+
+    assert!(sim.spread(&fishes) < initial_spread);
+}
+
+#[test]
+fn friction_decays_velocity() {
+    let mut sim = SimHarness::new(1);
+    let fish = sim.spawn_calm_npc(Vec3::ZERO);
+    sim.set_velocity(fish, Vec3::new(10.0, 0.0, 0.0));
+
+    sim.step(60);
+
+    let velocity = sim.velocity(fish);
+    assert!(velocity.length() < 10.0);
+    assert!(velocity.x > 0.0);
+    assert!(velocity.y.abs() < f32::EPSILON);
+}
+
+#[test]
+fn same_seed_gives_same_trajectory() {
+    let run = || {
+        let mut sim = SimHarness::new(7);
+        let fishes: Vec<Entity> = (0..8)
+            .map(|i| sim.spawn_npc(Vec3::new(20.0 * i as f32, 0.0, 0.0)))
+            .collect();
+        sim.step(120);
+        fishes.iter().map(|e| sim.position(*e)).collect::<Vec<_>>()
+    };
+
+    assert_eq!(run(), run());
+}
+
+#[test]
+fn forces_do_not_depend_on_thread_count() {
+    assert_eq!(trajectory_with_threads(1), trajectory_with_threads(4));
+}
+
+/// Runs [`thread_count_trajectory`] in a new process, since the task pools the
+/// forces run on are global and can only be sized once per process.
+fn trajectory_with_threads(threads: usize) -> String {
+    let output = Command::new(env::current_exe().unwrap())
+        .args([
+            "thread_count_trajectory",
+            "--exact",
+            "--ignored",
+            "--nocapture",
+        ])
+        .env(THREADS_VAR, threads.to_string())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .find_map(|line| line.strip_prefix(POSITIONS_PREFIX))
+        .map(str::to_string)
+        .expect("no positions printed")
+}
+
+#[test]
+#[ignore = "run by forces_do_not_depend_on_thread_count"]
+fn thread_count_trajectory() {
+    let threads: usize = match env::var(THREADS_VAR) {
+        Ok(threads) => threads.parse().unwrap(),
+        Err(_) => return,
+    };
+    ComputeTaskPool::init(|| TaskPoolBuilder::new().num_threads(threads).build());
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()
+        .unwrap();
+
+    let mut sim = SimHarness::new(3);
+    let fishes: Vec<Entity> = (0..3_000)
+        .map(|i| {
+            let fish = sim.spawn_npc(Vec3::new(3.0 * (i % 60) as f32, 3.0 * (i / 60) as f32, 0.0));
+            sim.set_group(fish, Some(2_000 + i % 3));
+            fish
+        })
+        .collect();
+    sim.step(30);
+
+    let positions: Vec<Vec3> = fishes.iter().map(|e| sim.position(*e)).collect();
+    println!("{}{:?}", POSITIONS_PREFIX, positions);
+}
@@ -0,0 +1,31 @@
+use homekoi::{
+    random::PondRng,
+    variety::{KoiVariety, VarietySettings},
+};
+
+#[test]
+fn choice_is_deterministic_for_a_seed() {
+    let settings = VarietySettings::default();
+    let mut a = PondRng::new(7);
+    let mut b = PondRng::new(7);
+
+    for _ in 0..100 {
+        assert_eq!(settings.choose(a.rng()), settings.choose(b.rng()));
+    }
+}
+
+#[test]
+fn choice_follows_distribution_and_size_range() {
+    let settings = VarietySettings {
+        distribution: vec![(KoiVariety::Ogon, 1.0), (KoiVariety::Showa, 0.0)],
+        min_size: 0.5,
+        max_size: 2.0,
+    };
+    let mut pond_rng = PondRng::new(7);
+
+    for _ in 0..100 {
+        let (variety, size) = settings.choose(pond_rng.rng());
+        assert_eq!(variety, KoiVariety::Ogon);
+        assert!((0.5..=2.0).contains(&size.0));
+    }
+}